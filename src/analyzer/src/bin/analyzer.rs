@@ -1,8 +1,12 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::time::Duration;
 use structopt::StructOpt;
 
-use analyzer::{call_graph::*, feature_extraction::*, options::*, slicer::*, symbolic_execution::*, utils::*};
+use analyzer::{
+  call_graph::*, feature_extraction::*, incremental::*, options::*, slicer::*, symbolic_execution::*, target_spec::*,
+  utils::*,
+};
 
 #[derive(StructOpt, Debug, Clone)]
 #[structopt(name = "analyzer")]
@@ -35,6 +39,20 @@ pub struct Options {
   #[structopt(long, takes_value = true, default_value = "1000", value_name = "MAX_AVG_NUM_BLOCKS")]
   pub max_avg_num_blocks: usize,
 
+  /// Maximum number of call sites per target. Call sites are sampled stratified by the source location of the caller
+  /// when exceeded
+  #[structopt(long, takes_value = true, value_name = "MAX_EDGES_PER_TARGET")]
+  pub max_edges_per_target: Option<usize>,
+
+  /// Strata used when sampling call sites, either directory or file
+  #[structopt(
+    long,
+    takes_value = true,
+    default_value = "directory",
+    value_name = "SAMPLING_STRATUM"
+  )]
+  pub sampling_stratum: SamplingStratum,
+
   /// Print call graph
   #[structopt(long)]
   pub print_call_graph: bool,
@@ -48,6 +66,14 @@ pub struct Options {
   )]
   pub slice_depth: usize,
 
+  /// Increase the slice depth of call edges whose target result or arguments escape the caller
+  #[structopt(long)]
+  pub adaptive_slice_depth: bool,
+
+  /// Maximum slice depth when using adaptive slice depth
+  #[structopt(long, takes_value = true, default_value = "3", value_name = "MAX_SLICE_DEPTH")]
+  pub max_slice_depth: usize,
+
   /// Execute only slice
   #[structopt(long, takes_value = true, value_name = "EXECUTE_ONLY_SLICE_ID")]
   pub execute_only_slice_id: Option<usize>,
@@ -61,10 +87,42 @@ pub struct Options {
   #[structopt(long, takes_value = true, value_name = "EXCLUDE_TARGET")]
   pub target_exclusion_filter: Option<String>,
 
+  /// TOML or JSON file specifying the targets and their option overrides. Replaces the target inclusion filter
+  #[structopt(long, takes_value = true, parse(try_from_str = TargetSpec::from_path), value_name = "TARGET_SPEC")]
+  pub target_spec: Option<TargetSpec>,
+
   /// Entry location filters. In the form of Regex if the option `use_regex_filter` is supplied
   #[structopt(long, takes_value = true, value_name = "ENTRY_LOCATION")]
   pub entry_filter: Option<String>,
 
+  /// Policies of functions that can be slice entries, separated by comma. Available policies are any, external,
+  /// address-taken and known-pattern
+  #[structopt(
+    long,
+    takes_value = true,
+    use_delimiter = true,
+    default_value = "any",
+    value_name = "ENTRY_POLICY"
+  )]
+  pub entry_policy: Vec<EntryPolicy>,
+
+  /// Regex of known entry function names used by the known-pattern entry policy
+  #[structopt(long, takes_value = true, value_name = "ENTRY_PATTERNS")]
+  pub entry_patterns: Option<String>,
+
+  /// Stop climbing at the first caller matching an entry policy other than any, before reaching the slice depth
+  #[structopt(long)]
+  pub stop_at_entries: bool,
+
+  /// Number of callers to climb past the slice depth to reach a function matching the entry policies. Call edges
+  /// without such an entry are dropped
+  #[structopt(long, takes_value = true, default_value = "2", value_name = "MAX_ENTRY_CLIMB")]
+  pub max_entry_climb: usize,
+
+  /// Maximum number of entries per call edge. Entries are sampled using the random seed when exceeded
+  #[structopt(long, takes_value = true, value_name = "MAX_ENTRIES_PER_EDGE")]
+  pub max_entries_per_edge: Option<usize>,
+
   /// Use regex in the filters
   #[structopt(long)]
   pub use_regex_filter: bool,
@@ -73,6 +131,25 @@ pub struct Options {
   #[structopt(long)]
   pub no_reduce_slice: bool,
 
+  /// Policies relating a function to the target during slice reduction, separated by comma. Available policies
+  /// are name-prefix, signature-structs, body-structs, data-flow and same-file
+  #[structopt(
+    long,
+    takes_value = true,
+    use_delimiter = true,
+    default_value = "name-prefix,signature-structs",
+    value_name = "RELATEDNESS_POLICY"
+  )]
+  pub relatedness_policy: Vec<RelatednessPolicy>,
+
+  /// Length of the name prefix used by the name-prefix relatedness policy
+  #[structopt(long, takes_value = true, default_value = "1", value_name = "PREFIX_LENGTH")]
+  pub relatedness_prefix_length: usize,
+
+  /// Dump the reason why each function is kept or dropped by slice reduction
+  #[structopt(long)]
+  pub dump_reduction_report: bool,
+
   /// Use batch execution. Especially useful when applying to large dataset
   #[structopt(long)]
   pub use_batch: bool,
@@ -92,6 +169,14 @@ pub struct Options {
   #[structopt(long, takes_value = true, default_value = "50", value_name = "MAX_WORK")]
   pub max_work: usize,
 
+  /// Number of times each loop can be unrolled in block traces and execution
+  #[structopt(long, takes_value = true, default_value = "0", value_name = "LOOP_UNROLL_BOUND")]
+  pub loop_unroll_bound: usize,
+
+  /// Number of levels each recursive function can be unrolled in execution
+  #[structopt(long, takes_value = true, default_value = "0", value_name = "MAX_RECURSION_DEPTH")]
+  pub max_recursion_depth: usize,
+
   /// The maximum number of generated trace per slice
   #[structopt(long, takes_value = true, default_value = "50", value_name = "MAX_TRACE_PER_SLICE")]
   pub max_trace_per_slice: usize,
@@ -119,9 +204,37 @@ pub struct Options {
   #[structopt(long)]
   pub print_trace: bool,
 
+  /// Dump the path constraints of each trace as a SMT-LIB2 query
+  #[structopt(long)]
+  pub dump_smt: bool,
+
   #[structopt(long)]
   pub no_prefilter_block_trace: bool,
 
+  /// Dump traces reaching the target that are cut before returning, marked as truncated
+  #[structopt(long)]
+  pub keep_truncated_traces: bool,
+
+  /// Guide the execution after the target call toward each exit of the caller
+  #[structopt(long)]
+  pub post_target_guidance: bool,
+
+  /// Only check path constraints at the end of execution instead of at every branch
+  #[structopt(long)]
+  pub no_prune_infeasible_branch: bool,
+
+  /// Solver checking path constraints: z3, smt-lib or always-sat
+  #[structopt(long, takes_value = true, default_value = "z3", value_name = "SOLVER")]
+  pub solver: SolverKind,
+
+  /// Command starting the external solver used by `--solver smt-lib`
+  #[structopt(long, takes_value = true, default_value = "z3 -in", value_name = "SOLVER_COMMAND")]
+  pub solver_command: String,
+
+  /// Milliseconds given to the external solver for each query, after which the path is kept
+  #[structopt(long, takes_value = true, default_value = "10000", value_name = "MILLISECONDS")]
+  pub solver_timeout: u64,
+
   #[structopt(long)]
   pub no_feature: bool,
 
@@ -135,6 +248,14 @@ pub struct Options {
     value_name = "CAUSALITY_DICTIONARY_SIZE"
   )]
   pub causality_dictionary_size: usize,
+
+  /// Byte code file of the previous version. Slices whose functions are unchanged reuse the previous traces
+  #[structopt(long, takes_value = true, value_name = "PREV_INPUT")]
+  pub incremental_prev_input: Option<String>,
+
+  /// Output directory of the analysis on the previous version
+  #[structopt(long, takes_value = true, value_name = "PREV_OUTPUT")]
+  pub incremental_prev_output: Option<String>,
 }
 
 impl GeneralOptions for Options {
@@ -164,6 +285,16 @@ impl IOOptions for Options {
   }
 }
 
+impl IncrementalOptions for Options {
+  fn prev_input_path(&self) -> Option<PathBuf> {
+    self.incremental_prev_input.as_ref().map(PathBuf::from)
+  }
+
+  fn prev_output_path(&self) -> Option<PathBuf> {
+    self.incremental_prev_output.as_ref().map(PathBuf::from)
+  }
+}
+
 impl Options {
   fn target_num_slices_map_path(&self) -> Option<PathBuf> {
     if let Some(filename) = &self.target_num_slices_map_file {
@@ -196,10 +327,38 @@ impl SlicerOptions for Options {
     self.slice_depth as usize
   }
 
+  fn adaptive_slice_depth(&self) -> bool {
+    self.adaptive_slice_depth
+  }
+
+  fn max_slice_depth(&self) -> usize {
+    self.max_slice_depth
+  }
+
   fn entry_filter(&self) -> &Option<String> {
     &self.entry_filter
   }
 
+  fn entry_policies(&self) -> &Vec<EntryPolicy> {
+    &self.entry_policy
+  }
+
+  fn entry_patterns(&self) -> &Option<String> {
+    &self.entry_patterns
+  }
+
+  fn stop_at_entries(&self) -> bool {
+    self.stop_at_entries
+  }
+
+  fn max_entry_climb(&self) -> usize {
+    self.max_entry_climb
+  }
+
+  fn max_entries_per_edge(&self) -> Option<usize> {
+    self.max_entries_per_edge
+  }
+
   fn target_inclusion_filter(&self) -> &Option<String> {
     &self.target_inclusion_filter
   }
@@ -208,6 +367,10 @@ impl SlicerOptions for Options {
     &self.target_exclusion_filter
   }
 
+  fn target_spec(&self) -> &Option<TargetSpec> {
+    &self.target_spec
+  }
+
   fn use_regex_filter(&self) -> bool {
     self.use_regex_filter
   }
@@ -215,6 +378,26 @@ impl SlicerOptions for Options {
   fn max_avg_num_blocks(&self) -> usize {
     self.max_avg_num_blocks
   }
+
+  fn max_edges_per_target(&self) -> Option<usize> {
+    self.max_edges_per_target
+  }
+
+  fn sampling_stratum(&self) -> SamplingStratum {
+    self.sampling_stratum
+  }
+
+  fn relatedness_policies(&self) -> &Vec<RelatednessPolicy> {
+    &self.relatedness_policy
+  }
+
+  fn relatedness_prefix_length(&self) -> usize {
+    self.relatedness_prefix_length
+  }
+
+  fn dump_reduction_report(&self) -> bool {
+    self.dump_reduction_report
+  }
 }
 
 impl SymbolicExecutionOptions for Options {
@@ -226,6 +409,14 @@ impl SymbolicExecutionOptions for Options {
     self.max_work
   }
 
+  fn loop_unroll_bound(&self) -> usize {
+    self.loop_unroll_bound
+  }
+
+  fn max_recursion_depth(&self) -> usize {
+    self.max_recursion_depth
+  }
+
   fn no_random_work(&self) -> bool {
     self.no_random_work
   }
@@ -250,6 +441,30 @@ impl SymbolicExecutionOptions for Options {
     self.no_prefilter_block_trace
   }
 
+  fn keep_truncated_traces(&self) -> bool {
+    self.keep_truncated_traces
+  }
+
+  fn post_target_guidance(&self) -> bool {
+    self.post_target_guidance
+  }
+
+  fn no_prune_infeasible_branch(&self) -> bool {
+    self.no_prune_infeasible_branch
+  }
+
+  fn solver(&self) -> SolverKind {
+    self.solver
+  }
+
+  fn solver_command(&self) -> &str {
+    self.solver_command.as_str()
+  }
+
+  fn solver_timeout(&self) -> u64 {
+    self.solver_timeout
+  }
+
   fn print_block_trace(&self) -> bool {
     self.print_block_trace
   }
@@ -257,6 +472,14 @@ impl SymbolicExecutionOptions for Options {
   fn print_trace(&self) -> bool {
     self.print_trace
   }
+
+  fn dump_smt(&self) -> bool {
+    self.dump_smt
+  }
+
+  fn target_spec(&self) -> &Option<TargetSpec> {
+    &self.target_spec
+  }
 }
 
 impl FeatureExtractorOptions for Options {
@@ -271,6 +494,9 @@ fn main() -> Result<(), String> {
     println!("{:?}", options);
   }
 
+  // Fail early on malformed entry patterns
+  let entry_matcher = EntryMatcher::new(&options)?;
+
   // Load a logging context
   let mut logging_ctx = LoggingContext::new(&options)?;
 
@@ -294,15 +520,39 @@ fn main() -> Result<(), String> {
 
   // Check if we need to "redo" the symbolic execution
   let target_num_slices_map = if !options.feature_only {
+    // Fail early if the solver cannot be used by the execution
+    Solver::validate(
+      options.solver(),
+      options.solver_command(),
+      Duration::from_millis(options.solver_timeout()),
+    )?;
+
     // Generate slices
     logging_ctx.log_generated_call_edges(target_edges_map.num_elements())?;
-    let target_slices_map = TargetSlicesMap::from_target_edges_map(&target_edges_map, &call_graph, &options);
+    let target_slices_map =
+      TargetSlicesMap::from_target_edges_map(&target_edges_map, &call_graph, &entry_matcher, &options);
     let target_num_slices_map = target_slices_map.keyed_num_elements();
 
     // Dump slices
     logging_ctx.log_generated_slices(target_slices_map.num_elements())?;
     target_slices_map.dump(&options);
 
+    // Reuse the traces of the unchanged slices from the previous version
+    let reused_slices = match (options.prev_input_path(), options.prev_output_path()) {
+      (Some(prev_input_path), _) => {
+        logging_ctx.log_loading_prev_bc()?;
+        let prev_llmod = llctx.load_module(&prev_input_path).map_err(|err| err.to_string())?;
+        let incr_ctx = IncrementalContext::new(&llmod, &prev_llmod);
+        let reused_slices = incr_ctx.reuse_slices(&target_slices_map, &options)?;
+        logging_ctx.log_reused_slices(incr_ctx.num_changed_functions(), reused_slices.num_reused())?;
+        reused_slices
+      }
+      (None, Some(_)) => {
+        return Err("Must provide the byte code of the previous version with its output directory".to_string())
+      }
+      (None, None) => ReusedSlices::new(),
+    };
+
     if let Some(slice_id) = &options.execute_only_slice_id {
       let func_name = if let Some(func_name) = &options.execute_only_slice_function_name {
         func_name
@@ -345,7 +595,8 @@ fn main() -> Result<(), String> {
       for (i, target_slices_map) in target_slices_map.batches(options.use_batch, options.batch_size) {
         // Generate slices from the edges
         logging_ctx.log_executing_batch(i, options.use_batch, target_slices_map.num_elements())?;
-        let sym_exec_ctx = SymbolicExecutionContext::new(&llmod, &call_graph, &options);
+        let sym_exec_ctx =
+          SymbolicExecutionContext::new(&llmod, &call_graph, &options).with_reused_slices(&reused_slices);
         let metadata = sym_exec_ctx.execute_target_slices_map(target_slices_map);
         global_metadata = global_metadata.combine(metadata.clone());
         logging_ctx.log_finished_execution_batch(i, options.use_batch, metadata)?;
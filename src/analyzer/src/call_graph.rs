@@ -48,9 +48,6 @@ impl<'ctx> CallGraphTrait<'ctx> for CallGraphRaw<'ctx> {
     self.retain_nodes(move |this, node_id| {
       let node_name = this[node_id].simp_name();
       let is_llvm_intrinsics = node_name.contains("llvm.");
-      if this[node_id].name().contains("va") {
-        println!("{}, {}", node_name, is_llvm_intrinsics);
-      }
       !is_llvm_intrinsics
     });
   }
@@ -206,8 +203,8 @@ impl<'ctx> CallGraph<'ctx> {
         .clone();
       for b in caller.iter_blocks() {
         for i in b.iter_instructions() {
-          match i {
-            Instruction::Call(call_instr) => {
+          match i.as_call_like() {
+            Some(call_instr) => {
               if !options.remove_llvm_funcs() || !call_instr.is_intrinsic_call() {
                 match call_instr.callee_function() {
                   Some(callee) => {
@@ -7,12 +7,14 @@ use std::path::PathBuf;
 
 use crate::feature_extractors::*;
 use crate::options::*;
-use crate::semantics::boxed::*;
+use crate::semantics::{boxed::*, TypeInfo};
 use crate::utils::*;
 
 #[derive(Deserialize)]
 pub struct Slice {
   pub instr: String,
+  #[serde(default)]
+  pub instr_loc: Option<DebugLocation>,
   pub entry: String,
   pub caller: String,
   pub callee: String,
@@ -24,14 +26,68 @@ impl Slice {}
 #[derive(Deserialize)]
 pub struct Instr {
   pub loc: String,
+  #[serde(default)]
+  pub debug_loc: Option<DebugLocation>,
+
+  /// Functions on the stack at the instruction, outermost first
+  #[serde(default)]
+  pub frames: Vec<String>,
   pub sem: Semantics,
   pub res: Option<Value>,
+
+  /// Type of the result, absent in traces dumped by older analyzers
+  #[serde(default)]
+  pub ty: Option<TypeInfo>,
+
+  /// Types of the arguments of a call
+  #[serde(default)]
+  pub arg_tys: Vec<TypeInfo>,
+}
+
+#[derive(Deserialize)]
+pub struct Constraint {
+  pub cond: Comparison,
+  pub branch: bool,
+  pub loc: String,
+
+  /// Index of the branch instruction in the trace
+  pub node: usize,
+}
+
+/// Source-level name of a value or, prefixed by `&`, of a location
+#[derive(Deserialize)]
+pub struct ValueName {
+  pub value: Value,
+  pub name: String,
 }
 
 #[derive(Deserialize)]
 pub struct Trace {
   pub target: usize,
+
+  /// Every call to the target, absent in traces dumped by older analyzers
+  #[serde(default)]
+  pub targets: Vec<usize>,
   pub instrs: Vec<Instr>,
+
+  /// Whether the trace ends at a call that never returns
+  #[serde(default)]
+  pub exited: bool,
+
+  /// Why the trace is cut before returning, if it is a kept truncated trace
+  #[serde(default)]
+  pub truncated: Option<String>,
+
+  /// Deepest recursion the trace steps into
+  #[serde(default)]
+  pub recursion_depth: usize,
+
+  /// Path constraints, absent in traces dumped by older analyzers
+  #[serde(default)]
+  pub constraints: Vec<Constraint>,
+
+  #[serde(default)]
+  pub names: Vec<ValueName>,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq)]
@@ -63,10 +119,37 @@ impl Trace {
     self.target_instr().sem.call_arg(index)
   }
 
+  pub fn target_result_type(&self) -> Option<&TypeInfo> {
+    self.target_instr().ty.as_ref()
+  }
+
+  pub fn target_arg_type(&self, index: usize) -> Option<&TypeInfo> {
+    self.target_instr().arg_tys.get(index)
+  }
+
+  /// Source-level name of the value, e.g. `dev->priv->lock`
+  pub fn value_name(&self, value: &Value) -> Option<&String> {
+    self.names.iter().find(|n| &n.value == value).map(|n| &n.name)
+  }
+
   pub fn target_index(&self) -> usize {
     self.target
   }
 
+  /// Indices of every call to the target in the trace
+  pub fn occurrences(&self) -> Vec<usize> {
+    if self.targets.is_empty() {
+      vec![self.target]
+    } else {
+      self.targets.clone()
+    }
+  }
+
+  /// Constraints of the branches taken before the target
+  pub fn constraints_before_target(&self) -> Vec<&Constraint> {
+    self.constraints.iter().filter(|c| c.node < self.target).collect()
+  }
+
   pub fn iter_instrs(&self, dir: TraceIterDirection) -> Vec<(usize, &Instr)> {
     if dir.is_forward() {
       self.instrs.iter().enumerate().collect()
@@ -102,6 +185,30 @@ pub trait FeatureExtractor: Send + Sync {
   fn finalize(&mut self);
 
   fn extract(&self, slice_id: usize, slice: &Slice, trace: &Trace) -> serde_json::Value;
+
+  /// Whether the extractor is also run at the other occurrences of the target
+  /// to aggregate the features across them
+  fn aggregates_occurrences(&self) -> bool {
+    false
+  }
+
+  /// Features at the occurrence of the target at the index of the trace, for
+  /// the extractors aggregating occurrences
+  fn extract_at_occurrence(
+    &self,
+    _slice_id: usize,
+    _slice: &Slice,
+    _trace: &Trace,
+    _target: usize,
+  ) -> serde_json::Value {
+    serde_json::Value::Null
+  }
+
+  /// Features across all the occurrences of the target, given the features
+  /// extracted at each of them
+  fn aggregate(&self, _occurrences: &[serde_json::Value]) -> serde_json::Value {
+    serde_json::Value::Null
+  }
 }
 
 pub struct FeatureExtractors {
@@ -109,30 +216,47 @@ pub struct FeatureExtractors {
 }
 
 impl FeatureExtractors {
+  pub fn new(extractors: Vec<Box<dyn FeatureExtractor>>) -> Self {
+    Self { extractors }
+  }
+
   pub fn all(options: &impl FeatureExtractorOptions) -> Self {
-    Self {
-      extractors: vec![
-        Box::new(ReturnValueFeatureExtractor::new()),
-        Box::new(ReturnValueCheckFeatureExtractor::new()),
-        Box::new(ArgumentPreconditionFeatureExtractor::new(0)),
-        Box::new(ArgumentPreconditionFeatureExtractor::new(1)),
-        Box::new(ArgumentPreconditionFeatureExtractor::new(2)),
-        Box::new(ArgumentPreconditionFeatureExtractor::new(3)),
-        Box::new(ArgumentPreconditionFeatureExtractor::new(4)),
-        Box::new(ArgumentPreconditionFeatureExtractor::new(5)),
-        Box::new(ArgumentPreconditionFeatureExtractor::new(6)),
-        Box::new(ArgumentPostconditionFeatureExtractor::new(0)),
-        Box::new(ArgumentPostconditionFeatureExtractor::new(1)),
-        Box::new(ArgumentPostconditionFeatureExtractor::new(2)),
-        Box::new(ArgumentPostconditionFeatureExtractor::new(3)),
-        Box::new(ArgumentPostconditionFeatureExtractor::new(4)),
-        Box::new(ArgumentPostconditionFeatureExtractor::new(5)),
-        Box::new(ArgumentPostconditionFeatureExtractor::new(6)),
-        Box::new(CausalityFeatureExtractor::pre(options.causality_dictionary_size())),
-        Box::new(CausalityFeatureExtractor::post(options.causality_dictionary_size())),
-        Box::new(ControlFlowFeaturesExtractor::new()),
-      ],
-    }
+    #[cfg_attr(not(feature = "z3"), allow(unused_mut))]
+    let mut extractors: Vec<Box<dyn FeatureExtractor>> = vec![
+      Box::new(ReturnValueFeatureExtractor::new()),
+      Box::new(ReturnValueCheckFeatureExtractor::new()),
+      Box::new(ArgumentPreconditionFeatureExtractor::new(0)),
+      Box::new(ArgumentPreconditionFeatureExtractor::new(1)),
+      Box::new(ArgumentPreconditionFeatureExtractor::new(2)),
+      Box::new(ArgumentPreconditionFeatureExtractor::new(3)),
+      Box::new(ArgumentPreconditionFeatureExtractor::new(4)),
+      Box::new(ArgumentPreconditionFeatureExtractor::new(5)),
+      Box::new(ArgumentPreconditionFeatureExtractor::new(6)),
+      Box::new(ArgumentPostconditionFeatureExtractor::new(0)),
+      Box::new(ArgumentPostconditionFeatureExtractor::new(1)),
+      Box::new(ArgumentPostconditionFeatureExtractor::new(2)),
+      Box::new(ArgumentPostconditionFeatureExtractor::new(3)),
+      Box::new(ArgumentPostconditionFeatureExtractor::new(4)),
+      Box::new(ArgumentPostconditionFeatureExtractor::new(5)),
+      Box::new(ArgumentPostconditionFeatureExtractor::new(6)),
+      Box::new(CausalityFeatureExtractor::pre(options.causality_dictionary_size())),
+      Box::new(CausalityFeatureExtractor::post(options.causality_dictionary_size())),
+      Box::new(ControlFlowFeaturesExtractor::new()),
+    ];
+
+    // Value ranges are only implied with z3
+    #[cfg(feature = "z3")]
+    extractors.extend(vec![
+      Box::new(ValueRangeFeatureExtractor::ret()) as Box<dyn FeatureExtractor>,
+      Box::new(ValueRangeFeatureExtractor::arg(0)),
+      Box::new(ValueRangeFeatureExtractor::arg(1)),
+      Box::new(ValueRangeFeatureExtractor::arg(2)),
+      Box::new(ValueRangeFeatureExtractor::arg(3)),
+      Box::new(ValueRangeFeatureExtractor::arg(4)),
+      Box::new(ValueRangeFeatureExtractor::arg(5)),
+      Box::new(ValueRangeFeatureExtractor::arg(6)),
+    ]);
+    Self { extractors }
   }
 
   pub fn extractors_for_target<'ctx>(
@@ -161,10 +285,51 @@ impl FeatureExtractors {
     }
   }
 
+  /// Features at the first occurrence of the target. The extractors that
+  /// aggregate occurrences are also run at the other ones: the aggregated
+  /// features are named `<extractor>.all`, and when the target is called
+  /// several times the features at each occurrence are listed under `occurrences`
   pub fn extract_features(&self, slice_id: usize, slice: &Slice, trace: &Trace) -> serde_json::Value {
     let mut map = serde_json::Map::new();
-    for extractor in &self.extractors {
-      map.insert(extractor.name(), extractor.extract(slice_id, &slice, &trace));
+    let features = self
+      .extractors
+      .iter()
+      .map(|extractor| extractor.extract(slice_id, slice, trace))
+      .collect::<Vec<_>>();
+    for (extractor, feature) in self.extractors.iter().zip(&features) {
+      map.insert(extractor.name(), feature.clone());
+    }
+
+    // Only the aggregating extractors are run at the other occurrences
+    let occurrences = trace.occurrences();
+    let other_targets = occurrences
+      .iter()
+      .cloned()
+      .filter(|&target| target != trace.target)
+      .collect::<Vec<_>>();
+    let mut occurrence_maps = vec![serde_json::Map::new(); other_targets.len() + 1];
+    for (extractor, feature) in self.extractors.iter().zip(features) {
+      if !extractor.aggregates_occurrences() {
+        continue;
+      }
+      let mut per_occurrence = vec![feature];
+      per_occurrence.extend(
+        other_targets
+          .iter()
+          .map(|&target| extractor.extract_at_occurrence(slice_id, slice, trace, target)),
+      );
+      map.insert(
+        format!("{}.all", extractor.name()),
+        extractor.aggregate(&per_occurrence),
+      );
+      for (occurrence_map, feature) in occurrence_maps.iter_mut().zip(per_occurrence) {
+        occurrence_map.insert(extractor.name(), feature);
+      }
+    }
+    map.insert("num_occurrences".to_string(), occurrences.len().into());
+    if occurrences.len() > 1 {
+      let occurrence_maps = occurrence_maps.into_iter().map(serde_json::Value::Object).collect();
+      map.insert("occurrences".to_string(), serde_json::Value::Array(occurrence_maps));
     }
     serde_json::Value::Object(map)
   }
@@ -4,6 +4,7 @@ use std::collections::HashSet;
 
 use crate::feature_extraction::*;
 use crate::semantics::boxed::*;
+use crate::utils::*;
 
 pub struct ArgumentPostconditionFeatureExtractor {
   pub index: usize,
@@ -21,7 +22,7 @@ impl FeatureExtractor for ArgumentPostconditionFeatureExtractor {
   }
 
   fn filter<'ctx>(&self, _: &String, target_type: FunctionType<'ctx>) -> bool {
-    self.index < target_type.num_argument_types()
+    target_type.may_have_argument(self.index)
   }
 
   fn init(&mut self, _: usize, _: &Slice, _: usize, _: &Trace) {}
@@ -3,6 +3,7 @@ use serde_json::json;
 
 use crate::feature_extraction::*;
 use crate::semantics::{boxed::*, *};
+use crate::utils::*;
 
 pub struct ArgumentPreconditionFeatureExtractor {
   pub index: usize,
@@ -20,7 +21,7 @@ impl FeatureExtractor for ArgumentPreconditionFeatureExtractor {
   }
 
   fn filter<'ctx>(&self, _: &String, target_type: FunctionType<'ctx>) -> bool {
-    self.index < target_type.num_argument_types()
+    target_type.may_have_argument(self.index)
   }
 
   fn init(&mut self, _: usize, _: &Slice, _: usize, _: &Trace) {}
@@ -10,3 +10,5 @@ mod retval;
 pub use retval::*;
 mod retval_check;
 pub use retval_check::*;
+mod value_range;
+pub use value_range::*;
@@ -37,21 +37,29 @@ impl FeatureExtractor for ReturnValueCheckFeatureExtractor {
     let mut compared_with_zero = false;
     let mut compared_with_non_const = false;
 
-    check(trace, &mut checked, &mut br_eq_zero, &mut br_neq_zero, &mut compared_with_zero, &mut compared_with_non_const);
+    check(trace, trace.target_index(), &mut checked, &mut br_eq_zero, &mut br_neq_zero, &mut compared_with_zero, &mut compared_with_non_const);
 
     self.slice_id_is_checked_map.entry(slice_id).and_modify(|c| *c |= checked).or_insert(checked);
   }
 
   fn finalize(&mut self) {}
 
-  fn extract(&self, slice_id: usize, _: &Slice, trace: &Trace) -> serde_json::Value {
+  fn extract(&self, slice_id: usize, slice: &Slice, trace: &Trace) -> serde_json::Value {
+    self.extract_at_occurrence(slice_id, slice, trace, trace.target_index())
+  }
+
+  fn aggregates_occurrences(&self) -> bool {
+    true
+  }
+
+  fn extract_at_occurrence(&self, slice_id: usize, _: &Slice, trace: &Trace, target: usize) -> serde_json::Value {
     let mut checked = false;
     let mut br_eq_zero = false;
     let mut br_neq_zero = false;
     let mut compared_with_zero = false;
     let mut compared_with_non_const = false;
 
-    check(trace, &mut checked, &mut br_eq_zero, &mut br_neq_zero, &mut compared_with_zero, &mut compared_with_non_const);
+    check(trace, target, &mut checked, &mut br_eq_zero, &mut br_neq_zero, &mut compared_with_zero, &mut compared_with_non_const);
 
     json!({
       "checked": checked,
@@ -62,11 +70,26 @@ impl FeatureExtractor for ReturnValueCheckFeatureExtractor {
       "compared_with_non_const": compared_with_non_const,
     })
   }
+
+  /// Whether every call to the target in the trace has its result checked
+  fn aggregate(&self, occurrences: &[serde_json::Value]) -> serde_json::Value {
+    let unchecked = occurrences
+      .iter()
+      .enumerate()
+      .filter(|(_, features)| !features["checked"].as_bool().unwrap_or(false))
+      .map(|(i, _)| i)
+      .collect::<Vec<_>>();
+    json!({
+      "all_checked": unchecked.is_empty(),
+      "any_unchecked": !unchecked.is_empty(),
+      "unchecked": unchecked,
+    })
+  }
 }
 
-pub fn check(trace: &Trace, checked: &mut bool, br_eq_zero: &mut bool, br_neq_zero: &mut bool, compared_with_zero: &mut bool, compared_with_non_const: &mut bool) {
-  let retval = trace.target_result().clone().unwrap();
-  instr_res_check(trace, &retval, trace.target_index(), checked, br_eq_zero, br_neq_zero, compared_with_zero, compared_with_non_const);
+pub fn check(trace: &Trace, target: usize, checked: &mut bool, br_eq_zero: &mut bool, br_neq_zero: &mut bool, compared_with_zero: &mut bool, compared_with_non_const: &mut bool) {
+  let retval = trace.instrs[target].res.clone().unwrap();
+  instr_res_check(trace, &retval, target, checked, br_eq_zero, br_neq_zero, compared_with_zero, compared_with_non_const);
 }
 
 pub fn instr_res_check(
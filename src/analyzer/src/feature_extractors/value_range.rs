@@ -0,0 +1,193 @@
+use llir::types::*;
+use serde_json::json;
+#[cfg(feature = "z3")]
+use std::collections::{BTreeSet, HashMap};
+#[cfg(feature = "z3")]
+use z3::ast::Ast;
+
+use crate::feature_extraction::*;
+use crate::semantics::{boxed::*, TypeInfo};
+#[cfg(feature = "z3")]
+use crate::symbolic_execution::thread_z3_context;
+use crate::utils::*;
+
+/// Where the value being ranged comes from
+#[derive(Copy, Clone)]
+pub enum RangedValue {
+  Arg(usize),
+  Ret,
+}
+
+/// Range & nullness of a target argument or of the return value implied by
+/// the path constraints, which also captures checks done through arithmetic
+/// or wrapper variables. Only registered with z3
+pub struct ValueRangeFeatureExtractor {
+  value: RangedValue,
+}
+
+impl ValueRangeFeatureExtractor {
+  pub fn arg(index: usize) -> Self {
+    Self {
+      value: RangedValue::Arg(index),
+    }
+  }
+
+  pub fn ret() -> Self {
+    Self {
+      value: RangedValue::Ret,
+    }
+  }
+}
+
+impl FeatureExtractor for ValueRangeFeatureExtractor {
+  fn name(&self) -> String {
+    match self.value {
+      RangedValue::Arg(index) => format!("arg.{}.range", index),
+      RangedValue::Ret => "ret.range".to_string(),
+    }
+  }
+
+  fn filter<'ctx>(&self, _: &String, target_type: FunctionType<'ctx>) -> bool {
+    match self.value {
+      RangedValue::Arg(index) => target_type.may_have_argument(index),
+      RangedValue::Ret => target_type.has_return_type(),
+    }
+  }
+
+  fn init(&mut self, _: usize, _: &Slice, _: usize, _: &Trace) {}
+
+  fn finalize(&mut self) {}
+
+  fn extract(&self, _: usize, _: &Slice, trace: &Trace) -> serde_json::Value {
+    let all_constraints = trace.constraints.iter().collect::<Vec<_>>();
+    match self.value {
+      RangedValue::Arg(index) => {
+        let ty = trace.target_arg_type(index);
+        match trace.target_arg(index) {
+          Some(arg) => json!({
+            "pre": value_range(arg, ty, &trace.constraints_before_target()),
+            "post": value_range(arg, ty, &all_constraints),
+          }),
+          None => json!({
+            "pre": ValueRange::default().to_json(),
+            "post": ValueRange::default().to_json(),
+          }),
+        }
+      }
+      RangedValue::Ret => match trace.target_result() {
+        Some(retval) => value_range(retval, trace.target_result_type(), &all_constraints),
+        None => ValueRange::default().to_json(),
+      },
+    }
+  }
+}
+
+#[derive(Default)]
+struct ValueRange {
+  /// Whether the value is a pointer, for which zero means null
+  ptr: bool,
+  zero: bool,
+  non_zero: bool,
+  pos: bool,
+  non_neg: bool,
+  neg: bool,
+  upper: Option<i64>,
+  lower: Option<i64>,
+}
+
+impl ValueRange {
+  fn to_json(&self) -> serde_json::Value {
+    json!({
+      "ptr": self.ptr,
+      "zero": self.zero,
+      "non_zero": self.non_zero,
+      "pos": self.pos,
+      "non_neg": self.non_neg,
+      "neg": self.neg,
+      "bounded_above": self.upper.is_some(),
+      "bounded_below": self.lower.is_some(),
+      "upper": self.upper,
+      "lower": self.lower,
+    })
+  }
+}
+
+/// The range of the value implied by the constraints
+#[cfg(feature = "z3")]
+pub fn value_range(value: &Value, ty: Option<&TypeInfo>, constraints: &[&Constraint]) -> serde_json::Value {
+  let z3_ctx = thread_z3_context();
+  let solver = z3::Solver::new(z3_ctx);
+  let mut symbol_map = HashMap::new();
+  let mut symbol_id = 0;
+
+  // Assert the path constraints, skipping the ones that cannot be encoded
+  for constraint in constraints {
+    if let Some(cond) = constraint.cond.into_z3_ast(&mut symbol_map, &mut symbol_id, z3_ctx) {
+      solver.assert(&if constraint.branch { cond } else { cond.not() });
+    }
+  }
+
+  // Nothing is implied when the value cannot be encoded or the path is unsat
+  let z3_value = match value.into_z3_ast(&mut symbol_map, &mut symbol_id, z3_ctx) {
+    Some(z3_value) => z3_value,
+    None => return ValueRange::default().to_json(),
+  };
+  if let z3::SatResult::Unsat = solver.check() {
+    return ValueRange::default().to_json();
+  }
+
+  // A property is implied when its negation is unsat under the constraints
+  let implied = |prop: z3::ast::Bool| {
+    solver.push();
+    solver.assert(&prop.not());
+    let result = solver.check();
+    solver.pop(1);
+    matches!(result, z3::SatResult::Unsat)
+  };
+  let int = |i: i64| z3::ast::Int::from_i64(z3_ctx, i);
+
+  // Bounds are searched among the constants the constraints compare with
+  let mut candidates = BTreeSet::new();
+  candidates.insert(0);
+  for constraint in constraints {
+    let (op0, op1) = constraint.cond.operands();
+    int_constants(op0, &mut candidates);
+    int_constants(op1, &mut candidates);
+  }
+
+  ValueRange {
+    ptr: ty.map_or(false, TypeInfo::is_pointer),
+    zero: implied(z3_value._eq(&int(0))),
+    non_zero: implied(z3_value._eq(&int(0)).not()),
+    pos: implied(z3_value.gt(&int(0))),
+    non_neg: implied(z3_value.ge(&int(0))),
+    neg: implied(z3_value.lt(&int(0))),
+    upper: candidates.iter().find(|c| implied(z3_value.le(&int(**c)))).cloned(),
+    lower: candidates
+      .iter()
+      .rev()
+      .find(|c| implied(z3_value.ge(&int(**c))))
+      .cloned(),
+  }
+  .to_json()
+}
+
+/// Nothing is implied without z3
+#[cfg(not(feature = "z3"))]
+pub fn value_range(_: &Value, _: Option<&TypeInfo>, _: &[&Constraint]) -> serde_json::Value {
+  serde_json::Value::Null
+}
+
+#[cfg(feature = "z3")]
+fn int_constants(value: &Value, constants: &mut BTreeSet<i64>) {
+  match value {
+    Value::Int(i) => {
+      constants.insert(*i);
+    }
+    Value::Bin { op0, op1, .. } => {
+      int_constants(op0, constants);
+      int_constants(op1, constants);
+    }
+    _ => {}
+  }
+}
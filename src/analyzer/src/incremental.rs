@@ -0,0 +1,398 @@
+//! Incremental re-analysis between two versions of a codebase
+//!
+//! Functions of the previous and the current byte code are compared using a
+//! structural hash. A slice whose functions are all unchanged is matched
+//! against the slices dumped by the previous run, and the traces & features
+//! of the matched slice are copied forward instead of being executed again.
+
+use llir::{values::*, Module};
+use llvm_sys::core::*;
+use llvm_sys::prelude::*;
+use llvm_sys::LLVMTypeKind;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+
+use crate::feature_extraction;
+use crate::options::*;
+use crate::slicer::*;
+use crate::utils::*;
+
+pub trait IncrementalOptions: IOOptions {
+  /// The byte code file of the previous version
+  fn prev_input_path(&self) -> Option<PathBuf>;
+
+  /// The output directory of the analysis on the previous version
+  fn prev_output_path(&self) -> Option<PathBuf>;
+}
+
+/// The output layout of the previous run. Everything is the same as the
+/// current options except that the output directory is the previous one
+struct PrevIOOptions<'a, O>
+where
+  O: IOOptions,
+{
+  options: &'a O,
+  prev_output_path: PathBuf,
+}
+
+impl<'a, O> IOOptions for PrevIOOptions<'a, O>
+where
+  O: IOOptions,
+{
+  fn input_path(&self) -> PathBuf {
+    self.options.input_path()
+  }
+
+  fn output_path(&self) -> PathBuf {
+    self.prev_output_path.clone()
+  }
+
+  fn default_package(&self) -> Option<&str> {
+    self.options.default_package()
+  }
+}
+
+pub trait FunctionStructuralHash {
+  /// Hash of the function body that does not depend on debug locations or
+  /// value names, so that a function is only considered changed if its code is.
+  /// The types of the values and the initializers of the referenced globals
+  /// are part of the code, so that changed struct layouts or ops tables are seen
+  fn structural_hash(&self) -> u64;
+}
+
+impl<'ctx> FunctionStructuralHash for Function<'ctx> {
+  fn structural_hash(&self) -> u64 {
+    let block_ids: HashMap<Block<'ctx>, usize> = self.iter_blocks().enumerate().map(|(i, b)| (b, i)).collect();
+    let instr_ids: HashMap<Instruction<'ctx>, usize> = self
+      .iter_instructions()
+      .enumerate()
+      .map(|(i, instr)| (instr, i))
+      .collect();
+    let mut hasher = StructuralHasher::new(instr_ids);
+    self.num_arguments().hash(&mut hasher.hasher);
+    hasher.hash_type(unsafe { LLVMTypeOf(self.value_ref()) });
+    for block in self.iter_blocks() {
+      for instr in block.iter_instructions() {
+        hasher.hash_instruction(&instr);
+      }
+      for next_block in block.successor_blocks() {
+        block_ids.get(&next_block).hash(&mut hasher.hasher);
+      }
+    }
+    hasher.hasher.finish()
+  }
+}
+
+struct StructuralHasher<'ctx> {
+  hasher: DefaultHasher,
+  instr_ids: HashMap<Instruction<'ctx>, usize>,
+
+  /// Struct types whose bodies are hashed, to stop at recursive structs
+  visited_structs: HashSet<LLVMTypeRef>,
+
+  /// Globals whose initializers are hashed, to stop at globals referring to each other
+  visited_globals: HashSet<Global<'ctx>>,
+}
+
+impl<'ctx> StructuralHasher<'ctx> {
+  fn new(instr_ids: HashMap<Instruction<'ctx>, usize>) -> Self {
+    Self {
+      hasher: DefaultHasher::new(),
+      instr_ids,
+      visited_structs: HashSet::new(),
+      visited_globals: HashSet::new(),
+    }
+  }
+
+  fn hash_instruction(&mut self, instr: &Instruction<'ctx>) {
+    let tag = match instr {
+      Instruction::Alloca(_) => "alloca".to_string(),
+      Instruction::Binary(bin) => format!("bin {:?}", bin.binary_opcode()),
+      Instruction::Unary(una) => format!("una {:?}", una.unary_opcode()),
+      Instruction::ICmp(icmp) => format!("icmp {:?}", icmp.predicate()),
+      Instruction::Call(call) => match call.callee_function() {
+        Some(func) => format!("call {}", func.simp_name()),
+        None => "call".to_string(),
+      },
+      Instruction::Other(_) => match instr.as_call_like() {
+        Some(invoke) => match invoke.callee_function() {
+          Some(func) => format!("invoke {}", func.simp_name()),
+          None => "invoke".to_string(),
+        },
+        None => "other".to_string(),
+      },
+      Instruction::Branch(_) => "br".to_string(),
+      Instruction::Switch(_) => "switch".to_string(),
+      Instruction::Return(_) => "ret".to_string(),
+      Instruction::Load(_) => "load".to_string(),
+      Instruction::Store(_) => "store".to_string(),
+      Instruction::GetElementPtr(_) => "gep".to_string(),
+      Instruction::Phi(_) => "phi".to_string(),
+      Instruction::Unreachable(_) => "unreachable".to_string(),
+      _ => "other".to_string(),
+    };
+    tag.hash(&mut self.hasher);
+
+    // The result type covers the allocated type of allocas and the loaded type
+    self.hash_type(unsafe { LLVMTypeOf(instr.value_ref()) });
+    for operand in instr.operands() {
+      // The operand type covers the source element type of GEPs
+      self.hash_type(unsafe { LLVMTypeOf(operand.value_ref()) });
+      match operand {
+        Operand::Instruction(i) => ("instr", self.instr_ids.get(&i)).hash(&mut self.hasher),
+        Operand::Argument(arg) => ("arg", arg.index()).hash(&mut self.hasher),
+        Operand::Constant(cons) => self.hash_constant(&cons),
+        _ => "operand".hash(&mut self.hasher),
+      }
+    }
+  }
+
+  fn hash_constant(&mut self, constant: &Constant<'ctx>) {
+    self.hash_type(unsafe { LLVMTypeOf(constant.value_ref()) });
+    match constant {
+      Constant::Int(i) => ("int", i.sext_value()).hash(&mut self.hasher),
+      Constant::Null(_) => "null".hash(&mut self.hasher),
+      Constant::Global(glob) => {
+        ("glob", glob.name()).hash(&mut self.hasher);
+        if self.visited_globals.insert(*glob) {
+          match glob.initializer() {
+            Some(init) => self.hash_constant(&init),
+            None => "extern".hash(&mut self.hasher),
+          }
+        }
+      }
+      Constant::Function(func) => ("func", func.simp_name()).hash(&mut self.hasher),
+      Constant::Struct(s) => {
+        "struct".hash(&mut self.hasher);
+        for element in s.elements() {
+          self.hash_constant(&element);
+        }
+      }
+      Constant::Array(a) => {
+        "array".hash(&mut self.hasher);
+        for element in a.elements() {
+          self.hash_constant(&element);
+        }
+      }
+      Constant::ConstExpr(ConstExpr::Binary(b)) => {
+        ("bin", format!("{:?}", b.opcode())).hash(&mut self.hasher);
+        self.hash_constant(&b.op0());
+        self.hash_constant(&b.op1());
+      }
+      Constant::ConstExpr(ConstExpr::Unary(u)) => self.hash_constant(&u.op0()),
+      Constant::ConstExpr(ConstExpr::GetElementPtr(g)) => {
+        "gep".hash(&mut self.hasher);
+        self.hash_constant(&g.location());
+        for index in g.indices() {
+          self.hash_constant(&index);
+        }
+      }
+      _ => "const".hash(&mut self.hasher),
+    }
+  }
+
+  /// Hash the type down to the integer widths and the bodies of the structs
+  fn hash_type(&mut self, ty: LLVMTypeRef) {
+    unsafe {
+      let kind = LLVMGetTypeKind(ty);
+      (kind as u32).hash(&mut self.hasher);
+      match kind {
+        LLVMTypeKind::LLVMIntegerTypeKind => LLVMGetIntTypeWidth(ty).hash(&mut self.hasher),
+        LLVMTypeKind::LLVMPointerTypeKind => self.hash_type(LLVMGetElementType(ty)),
+        LLVMTypeKind::LLVMArrayTypeKind => {
+          LLVMGetArrayLength(ty).hash(&mut self.hasher);
+          self.hash_type(LLVMGetElementType(ty));
+        }
+        LLVMTypeKind::LLVMVectorTypeKind => {
+          LLVMGetVectorSize(ty).hash(&mut self.hasher);
+          self.hash_type(LLVMGetElementType(ty));
+        }
+        LLVMTypeKind::LLVMStructTypeKind => {
+          if self.visited_structs.insert(ty) {
+            (LLVMIsPackedStruct(ty), LLVMIsOpaqueStruct(ty)).hash(&mut self.hasher);
+            let num_fields = LLVMCountStructElementTypes(ty);
+            num_fields.hash(&mut self.hasher);
+            for i in 0..num_fields {
+              self.hash_type(LLVMStructGetTypeAtIndex(ty, i));
+            }
+          }
+        }
+        LLVMTypeKind::LLVMFunctionTypeKind => {
+          LLVMIsFunctionVarArg(ty).hash(&mut self.hasher);
+          self.hash_type(LLVMGetReturnType(ty));
+          let num_params = LLVMCountParamTypes(ty) as usize;
+          let mut params = vec![std::ptr::null_mut(); num_params];
+          LLVMGetParamTypes(ty, params.as_mut_ptr());
+          for param in params {
+            self.hash_type(param);
+          }
+        }
+        _ => {}
+      }
+    }
+  }
+}
+
+/// Map from function name to the structural hash of that function
+pub type FunctionHashMap = HashMap<String, u64>;
+
+pub trait FunctionHashMapTrait: Sized {
+  fn from_module<'ctx>(module: &Module<'ctx>) -> Self;
+}
+
+impl FunctionHashMapTrait for FunctionHashMap {
+  fn from_module<'ctx>(module: &Module<'ctx>) -> Self {
+    module
+      .iter_functions()
+      .filter(|func| !func.is_declaration_only())
+      .map(|func| (func.name(), func.structural_hash()))
+      .collect()
+  }
+}
+
+/// Map from target name to the ids of the slices reused from previous run
+pub type ReusedSlices = HashMap<String, HashSet<usize>>;
+
+pub trait ReusedSlicesTrait {
+  fn is_reused(&self, target: &str, slice_id: usize) -> bool;
+
+  fn num_reused(&self) -> usize;
+}
+
+impl ReusedSlicesTrait for ReusedSlices {
+  fn is_reused(&self, target: &str, slice_id: usize) -> bool {
+    match self.get(target) {
+      Some(ids) => ids.contains(&slice_id),
+      None => false,
+    }
+  }
+
+  fn num_reused(&self) -> usize {
+    self.values().map(|ids| ids.len()).sum()
+  }
+}
+
+/// Identifies a slice across two versions: the entry, caller, callee, the
+/// location of the target call and the (sorted) functions inside of the slice
+type SliceKey = (String, String, String, String, Vec<String>);
+
+fn slice_key(slice: &Slice) -> SliceKey {
+  let mut functions = slice.functions.iter().map(|f| f.simp_name()).collect::<Vec<_>>();
+  functions.sort();
+  (
+    slice.entry.simp_name(),
+    slice.caller.simp_name(),
+    slice.callee.simp_name(),
+    slice.instr.debug_loc_string(),
+    functions,
+  )
+}
+
+fn prev_slice_key(slice: feature_extraction::Slice) -> SliceKey {
+  let mut functions = slice.functions;
+  functions.sort();
+  (slice.entry, slice.caller, slice.callee, slice.instr, functions)
+}
+
+pub struct IncrementalContext {
+  /// Names of the functions that are changed or newly added in current version
+  pub changed_functions: HashSet<String>,
+}
+
+impl IncrementalContext {
+  pub fn new<'ctx>(module: &Module<'ctx>, prev_module: &Module<'ctx>) -> Self {
+    let prev_hashes = FunctionHashMap::from_module(prev_module);
+    let changed_functions = FunctionHashMap::from_module(module)
+      .into_iter()
+      .filter(|(name, hash)| prev_hashes.get(name) != Some(hash))
+      .map(|(name, _)| name)
+      .collect();
+    Self { changed_functions }
+  }
+
+  pub fn num_changed_functions(&self) -> usize {
+    self.changed_functions.len()
+  }
+
+  pub fn is_unchanged(&self, slice: &Slice) -> bool {
+    slice
+      .functions
+      .iter()
+      .all(|func| !self.changed_functions.contains(&func.name()))
+  }
+
+  /// Copy the traces & features of every unchanged slice from the previous
+  /// output directory, returning the slices that do not need execution
+  pub fn reuse_slices<'ctx, O>(
+    &self,
+    target_slices_map: &TargetSlicesMap<'ctx>,
+    options: &O,
+  ) -> Result<ReusedSlices, String>
+  where
+    O: IncrementalOptions,
+  {
+    let prev_output_path = options
+      .prev_output_path()
+      .ok_or("Must provide the output directory of the previous version".to_string())?;
+    if prev_output_path == options.output_path() {
+      return Err("The output directory of the previous version must be different".to_string());
+    }
+    let prev_options = PrevIOOptions {
+      options,
+      prev_output_path,
+    };
+
+    let mut reused_slices = ReusedSlices::new();
+    for (target, slices) in target_slices_map {
+      let prev_slice_ids = load_prev_slice_ids(&prev_options, target);
+      for (slice_id, slice) in slices.iter().enumerate() {
+        if !self.is_unchanged(slice) {
+          continue;
+        }
+        if let Some(prev_slice_id) = prev_slice_ids.get(&slice_key(slice)) {
+          let prev_trace_dir = prev_options.trace_target_slice_dir(target, *prev_slice_id);
+          let trace_dir = options.trace_target_slice_dir(target, slice_id);
+          if prev_trace_dir.exists() {
+            copy_dir_all(&prev_trace_dir, &trace_dir)?;
+          } else {
+            fs::create_dir_all(&trace_dir).map_err(|_| "Cannot create trace function slice folder".to_string())?;
+          }
+          let prev_metadata_path = prev_options.trace_target_slice_metadata_path(target, *prev_slice_id);
+          if prev_metadata_path.exists() {
+            fs::copy(
+              &prev_metadata_path,
+              options.trace_target_slice_metadata_path(target, slice_id),
+            )
+            .map_err(|_| "Cannot copy slice metadata".to_string())?;
+          }
+          let prev_feature_dir = prev_options.feature_target_slice_dir(target, *prev_slice_id);
+          if prev_feature_dir.exists() {
+            copy_dir_all(&prev_feature_dir, &options.feature_target_slice_dir(target, slice_id))?;
+          }
+          reused_slices
+            .entry(target.clone())
+            .or_insert_with(HashSet::new)
+            .insert(slice_id);
+        }
+      }
+    }
+    Ok(reused_slices)
+  }
+}
+
+fn load_prev_slice_ids(prev_options: &impl IOOptions, target: &str) -> HashMap<SliceKey, usize> {
+  match fs::read_dir(prev_options.slice_target_dir(target)) {
+    Ok(paths) => paths
+      .filter_map(|path| {
+        let path = path.ok()?.path();
+        let slice_id = path.file_stem()?.to_str()?.parse::<usize>().ok()?;
+        let slice: feature_extraction::Slice = load_json_t(&path).ok()?;
+        Some((prev_slice_key(slice), slice_id))
+      })
+      .collect(),
+    _ => HashMap::new(),
+  }
+}
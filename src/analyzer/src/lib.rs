@@ -3,8 +3,10 @@ pub extern crate llir;
 pub mod call_graph;
 pub mod feature_extraction;
 pub mod feature_extractors;
+pub mod incremental;
 pub mod options;
 pub mod semantics;
 pub mod slicer;
 pub mod symbolic_execution;
+pub mod target_spec;
 pub mod utils;
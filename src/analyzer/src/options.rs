@@ -6,6 +6,19 @@ pub trait GeneralOptions {
   fn seed(&self) -> u64;
 }
 
+/// Directory name of a target. Demangled C++ names such as `operator/` or
+/// `foo<int, char>` can contain characters that are not safe in paths, which
+/// are replaced by `_`
+pub fn target_dir_name(target: &str) -> String {
+  target
+    .chars()
+    .map(|c| match c {
+      'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' | '.' | ':' | '~' => c,
+      _ => '_',
+    })
+    .collect()
+}
+
 pub trait IOOptions {
   fn input_path(&self) -> PathBuf;
 
@@ -25,7 +38,7 @@ pub trait IOOptions {
   }
 
   fn slice_target_dir(&self, target: &str) -> PathBuf {
-    self.with_package(self.slice_dir().join(target))
+    self.with_package(self.slice_dir().join(target_dir_name(target)))
   }
 
   fn slice_target_file_path(&self, target: &str, slice_id: usize) -> PathBuf {
@@ -33,7 +46,7 @@ pub trait IOOptions {
   }
 
   fn slice_target_package_dir(&self, target: &str, package: &str) -> PathBuf {
-    self.slice_dir().join(target).join(package)
+    self.slice_dir().join(target_dir_name(target)).join(package)
   }
 
   fn slice_target_package_file_path(&self, target: &str, package: &str, slice_id: usize) -> PathBuf {
@@ -47,7 +60,7 @@ pub trait IOOptions {
   }
 
   fn trace_target_dir(&self, target: &str) -> PathBuf {
-    self.with_package(self.trace_dir().join(target))
+    self.with_package(self.trace_dir().join(target_dir_name(target)))
   }
 
   fn trace_target_slice_dir(&self, target: &str, slice_id: usize) -> PathBuf {
@@ -60,8 +73,19 @@ pub trait IOOptions {
       .join(format!("{}.json", trace_id))
   }
 
+  /// Counts of the traces explored in a slice, next to the trace directory of the slice
+  fn trace_target_slice_metadata_path(&self, target: &str, slice_id: usize) -> PathBuf {
+    self
+      .trace_target_dir(target)
+      .join(format!("{}.metadata.json", slice_id))
+  }
+
   fn trace_target_package_slice_dir(&self, target: &str, package: &str, slice_id: usize) -> PathBuf {
-    self.trace_dir().join(target).join(package).join(slice_id.to_string())
+    self
+      .trace_dir()
+      .join(target_dir_name(target))
+      .join(package)
+      .join(slice_id.to_string())
   }
 
   fn trace_target_package_slice_file_path(
@@ -81,7 +105,7 @@ pub trait IOOptions {
   }
 
   fn feature_target_dir(&self, target: &str) -> PathBuf {
-    self.with_package(self.feature_dir().join(target))
+    self.with_package(self.feature_dir().join(target_dir_name(target)))
   }
 
   fn feature_target_slice_dir(&self, target: &str, slice_id: usize) -> PathBuf {
@@ -95,7 +119,11 @@ pub trait IOOptions {
   }
 
   fn feature_target_package_slice_dir(&self, target: &str, package: &str, slice_id: usize) -> PathBuf {
-    self.feature_dir().join(target).join(package).join(slice_id.to_string())
+    self
+      .feature_dir()
+      .join(target_dir_name(target))
+      .join(package)
+      .join(slice_id.to_string())
   }
 
   fn feature_target_package_slice_file_path(
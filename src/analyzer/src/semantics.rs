@@ -90,6 +90,77 @@ impl Branch {
   }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TypeKind {
+  Void,
+  Int,
+  Float,
+  Pointer,
+  Array,
+  Vector,
+  Struct,
+  Function,
+  Other,
+}
+
+/// LLVM type of a value
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TypeInfo {
+  pub kind: TypeKind,
+
+  /// Bit width of an integer
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub width: Option<u32>,
+
+  /// Name of a named struct, e.g. `struct.device`
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub struct_name: Option<String>,
+
+  /// Type pointed to by a pointer or element type of an array or vector
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub element: Option<Box<TypeInfo>>,
+}
+
+impl TypeInfo {
+  pub fn from_type(ty: llir::types::Type) -> Self {
+    use llir::types::*;
+    let (kind, width, struct_name, element) = match ty {
+      Type::Void(_) => (TypeKind::Void, None, None, None),
+      Type::Int(i) => (TypeKind::Int, Some(i.width()), None, None),
+      Type::Float(_) => (TypeKind::Float, None, None, None),
+      Type::Pointer(p) => (TypeKind::Pointer, None, None, Some(p.element_type())),
+      Type::Array(a) => (TypeKind::Array, None, None, Some(a.element_type())),
+      Type::Vector(v) => (TypeKind::Vector, None, None, Some(v.element_type())),
+      Type::Struct(StructType::NamedStruct(s)) => (TypeKind::Struct, None, Some(s.name()), None),
+      Type::Struct(_) => (TypeKind::Struct, None, None, None),
+      Type::Function(_) => (TypeKind::Function, None, None, None),
+      Type::Other(_) => (TypeKind::Other, None, None, None),
+    };
+    Self {
+      kind,
+      width,
+      struct_name,
+      element: element.map(|element| Box::new(Self::from_type(element))),
+    }
+  }
+
+  pub fn is_pointer(&self) -> bool {
+    self.kind == TypeKind::Pointer
+  }
+
+  pub fn is_int(&self) -> bool {
+    self.kind == TypeKind::Int
+  }
+
+  /// Name of the struct pointed to, e.g. `struct.device` for `struct device *`
+  pub fn pointee_struct_name(&self) -> Option<&String> {
+    match (&self.kind, &self.element) {
+      (TypeKind::Pointer, Some(element)) => element.struct_name.as_ref(),
+      _ => None,
+    }
+  }
+}
+
 macro_rules! decl_value_with_wrapper {
   ($wrapper:ident) => {
     #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -154,6 +225,7 @@ macro_rules! decl_value_with_wrapper {
         }
       }
 
+      #[cfg(feature = "z3")]
       pub fn into_z3_ast<'ctx>(
         &self,
         symbol_map: &mut HashMap<Value, z3::Symbol>,
@@ -191,16 +263,51 @@ macro_rules! decl_value_with_wrapper {
           }
         }
       }
+
+      /// SMT-LIB2 term of the value in the theory of integers, mirroring
+      /// `into_z3_ast`. Symbols are named `s<id>` after their id in the map
+      pub fn to_smtlib(&self, symbol_map: &mut HashMap<Value, usize>) -> Option<String> {
+        match self {
+          Value::Int(i) if *i < 0 => Some(format!("(- {})", (*i as i128).abs())),
+          Value::Int(i) => Some(i.to_string()),
+          Value::Null => Some("0".to_string()),
+          Value::Bin { op, op0, op1 } => {
+            let op = match op {
+              BinOp::Add => "+",
+              BinOp::Sub => "-",
+              BinOp::Mul => "*",
+              BinOp::UDiv | BinOp::SDiv => "div",
+              BinOp::URem | BinOp::SRem => "mod",
+              _ => return None,
+            };
+            let op0 = op0.to_smtlib(symbol_map)?;
+            let op1 = op1.to_smtlib(symbol_map)?;
+            Some(format!("({} {} {})", op, op0, op1))
+          }
+          Value::Unknown => None,
+          _ => {
+            let num_symbols = symbol_map.len();
+            let symbol_id = symbol_map.entry(self.clone()).or_insert(num_symbols);
+            Some(format!("s{}", symbol_id))
+          }
+        }
+      }
     }
 
-    #[derive(Debug, Clone)]
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
     pub struct Comparison {
+      #[serde(with = "PredicateDef")]
       pred: Predicate,
       op0: $wrapper<Value>,
       op1: $wrapper<Value>,
     }
 
     impl Comparison {
+      pub fn operands(&self) -> (&Value, &Value) {
+        (&self.op0, &self.op1)
+      }
+
+      #[cfg(feature = "z3")]
       pub fn into_z3_ast<'ctx>(
         &self,
         symbol_map: &mut HashMap<Value, z3::Symbol>,
@@ -223,6 +330,20 @@ macro_rules! decl_value_with_wrapper {
           _ => None,
         }
       }
+
+      pub fn to_smtlib(&self, symbol_map: &mut HashMap<Value, usize>) -> Option<String> {
+        let Comparison { pred, op0, op1 } = self;
+        let op0 = op0.to_smtlib(symbol_map)?;
+        let op1 = op1.to_smtlib(symbol_map)?;
+        match pred {
+          Predicate::EQ => Some(format!("(= {} {})", op0, op1)),
+          Predicate::NE => Some(format!("(not (= {} {}))", op0, op1)),
+          Predicate::SGE | Predicate::UGE => Some(format!("(>= {} {})", op0, op1)),
+          Predicate::SGT | Predicate::UGT => Some(format!("(> {} {})", op0, op1)),
+          Predicate::SLE | Predicate::ULE => Some(format!("(<= {} {})", op0, op1)),
+          Predicate::SLT | Predicate::ULT => Some(format!("(< {} {})", op0, op1)),
+        }
+      }
     }
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,103 @@
+use llir::{types::*, values::*};
+use petgraph::{graph::*, visit::*, Direction};
+use std::collections::HashSet;
+
+use crate::call_graph::*;
+use crate::utils::*;
+
+use super::options::*;
+
+pub trait AdaptiveSliceDepth {
+  /// Slice depth of the call edge. When the result or an argument of the call
+  /// escapes the caller (returned, or stored to an argument), the escaping value
+  /// is followed one level higher through the call sites of the caller, until
+  /// it does not escape anymore or `max_slice_depth` is reached. The result is
+  /// never lower than `slice_depth`
+  fn adaptive_slice_depth(&self, edge_id: EdgeIndex, options: &impl SlicerOptions) -> usize;
+}
+
+impl<'ctx> AdaptiveSliceDepth for CallGraph<'ctx> {
+  fn adaptive_slice_depth(&self, edge_id: EdgeIndex, options: &impl SlicerOptions) -> usize {
+    let (caller_id, _) = self.graph.edge_endpoints(edge_id).unwrap();
+    let mut call_sites = vec![(caller_id, self.graph[edge_id])];
+    let mut num_escaping_levels = 0;
+    while num_escaping_levels < options.max_slice_depth() {
+      let escaped_func_ids = call_sites
+        .iter()
+        .filter(|(_, instr)| call_escapes(*instr))
+        .map(|(func_id, _)| *func_id)
+        .collect::<HashSet<_>>();
+      if escaped_func_ids.is_empty() {
+        break;
+      }
+      num_escaping_levels += 1;
+      call_sites = escaped_func_ids
+        .into_iter()
+        .map(|func_id| -> Vec<_> {
+          self
+            .graph
+            .edges_directed(func_id, Direction::Incoming)
+            .map(|edge| (edge.source(), *edge.weight()))
+            .collect()
+        })
+        .flatten()
+        .collect();
+    }
+    num_escaping_levels.max(options.slice_depth())
+  }
+}
+
+/// Whether the result or an argument of the call escapes its parent function:
+/// the result is returned or stored to an argument, or a pointer argument,
+/// whose pointee the callee may modify, comes from an argument of the parent
+fn call_escapes<'ctx>(instr: CallInstruction<'ctx>) -> bool {
+  let func = instr.parent_function();
+  let holders = argument_holders(func);
+  let argument_escapes = instr.arguments().into_iter().any(|arg| match arg.get_type() {
+    Type::Pointer(_) => is_argument_rooted(arg, &holders),
+    _ => false,
+  });
+  if argument_escapes {
+    return true;
+  }
+  let seeds = vec![Instruction::Call(instr)].into_iter().collect::<HashSet<_>>();
+  let flows = forward_data_flow(func, seeds);
+  flows.iter().any(|i| match i {
+    Instruction::Return(_) => true,
+    Instruction::Store(st) => {
+      let stores_flow = match st.value() {
+        Operand::Instruction(value) => flows.contains(&value),
+        _ => false,
+      };
+      stores_flow && is_argument_rooted(st.location(), &holders)
+    }
+    _ => false,
+  })
+}
+
+/// Stack slots holding the arguments of the function, as in unoptimized byte code
+fn argument_holders<'ctx>(func: Function<'ctx>) -> HashSet<Instruction<'ctx>> {
+  func
+    .iter_instructions()
+    .filter_map(|instr| match instr {
+      Instruction::Store(st) => match (st.value(), st.location()) {
+        (Operand::Argument(_), Operand::Instruction(loc)) => Some(loc),
+        _ => None,
+      },
+      _ => None,
+    })
+    .collect()
+}
+
+fn is_argument_rooted<'ctx>(op: Operand<'ctx>, holders: &HashSet<Instruction<'ctx>>) -> bool {
+  match op {
+    Operand::Argument(_) => true,
+    Operand::Instruction(Instruction::GetElementPtr(gep)) => is_argument_rooted(gep.location(), holders),
+    Operand::Instruction(Instruction::Unary(una)) => is_argument_rooted(una.op0(), holders),
+    Operand::Instruction(Instruction::Load(ld)) => match ld.location() {
+      Operand::Instruction(loc) if holders.contains(&loc) => true,
+      loc => is_argument_rooted(loc, holders),
+    },
+    _ => false,
+  }
+}
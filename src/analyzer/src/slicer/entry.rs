@@ -0,0 +1,84 @@
+use llir::values::*;
+use regex::Regex;
+use std::str::FromStr;
+
+use crate::utils::*;
+
+use super::options::*;
+
+/// Entry patterns used by the `known-pattern` policy when no pattern is given:
+/// `main`, syscall handlers, module initializers and `probe`/`remove` callbacks
+pub const DEFAULT_ENTRY_PATTERNS: &str =
+  r"^(main|(__x64_|__ia32_|__arm64_|__se_|__do_)?sys_\w+|init_module|\w+_init|\w+_probe|\w+_remove)$";
+
+/// Policy deciding which functions can be the entry of a slice
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum EntryPolicy {
+  /// Any function reached after climbing `slice_depth` callers
+  Any,
+
+  /// Functions that are externally visible, i.e. not `static`
+  External,
+
+  /// Functions whose address is taken, e.g. callbacks registered in ops structs
+  AddressTaken,
+
+  /// Functions whose names match the known entry patterns
+  KnownPattern,
+}
+
+impl FromStr for EntryPolicy {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "any" => Ok(Self::Any),
+      "external" => Ok(Self::External),
+      "address-taken" => Ok(Self::AddressTaken),
+      "known-pattern" => Ok(Self::KnownPattern),
+      _ => Err(format!("Unknown entry policy {}", s)),
+    }
+  }
+}
+
+pub struct EntryMatcher {
+  policies: Vec<EntryPolicy>,
+  patterns: Regex,
+}
+
+impl EntryMatcher {
+  pub fn new(options: &impl SlicerOptions) -> Result<Self, String> {
+    let patterns = match options.entry_patterns() {
+      Some(patterns) => patterns.as_str(),
+      None => DEFAULT_ENTRY_PATTERNS,
+    };
+    let patterns = Regex::new(patterns).map_err(|_| "Cannot parse entry patterns regex".to_string())?;
+    Ok(Self {
+      policies: options.entry_policies().clone(),
+      patterns,
+    })
+  }
+
+  /// Whether the function can be used as a slice entry
+  pub fn matches(&self, func: &Function) -> bool {
+    self.policies.iter().any(|policy| self.matches_policy(func, *policy))
+  }
+
+  /// Whether the function matches a policy other than `any`, so that climbing
+  /// up the call graph can stop there with `stop_at_entries`
+  pub fn stops_at(&self, func: &Function) -> bool {
+    self
+      .policies
+      .iter()
+      .any(|policy| *policy != EntryPolicy::Any && self.matches_policy(func, *policy))
+  }
+
+  fn matches_policy(&self, func: &Function, policy: EntryPolicy) -> bool {
+    match policy {
+      EntryPolicy::Any => true,
+      EntryPolicy::External => func.is_externally_visible(),
+      EntryPolicy::AddressTaken => func.is_address_taken(),
+      EntryPolicy::KnownPattern => self.patterns.is_match(func.simp_name().as_str()),
+    }
+  }
+}
@@ -0,0 +1,13 @@
+mod depth;
+mod entry;
+mod options;
+mod relatedness;
+mod sampling;
+mod slicing;
+
+pub use depth::*;
+pub use entry::*;
+pub use options::*;
+pub use relatedness::*;
+pub use sampling::*;
+pub use slicing::*;
@@ -0,0 +1,55 @@
+use crate::options::*;
+use crate::target_spec::*;
+
+use super::entry::*;
+use super::relatedness::*;
+use super::sampling::*;
+
+pub trait SlicerOptions: GeneralOptions + Send + Sync {
+  fn no_reduce_slice(&self) -> bool;
+
+  fn slice_depth(&self) -> usize;
+
+  /// Increase the slice depth of the call edges whose target result or
+  /// arguments escape the caller, up to `max_slice_depth`
+  fn adaptive_slice_depth(&self) -> bool;
+
+  fn max_slice_depth(&self) -> usize;
+
+  fn entry_filter(&self) -> &Option<String>;
+
+  fn entry_policies(&self) -> &Vec<EntryPolicy>;
+
+  /// Regex of known entry names overriding the default ones
+  fn entry_patterns(&self) -> &Option<String>;
+
+  /// Stop climbing up the call graph at the first caller matching a policy
+  /// other than `any`, even before reaching `slice_depth`
+  fn stop_at_entries(&self) -> bool;
+
+  /// Number of callers to climb past `slice_depth` when the caller reached
+  /// there does not match the entry policies
+  fn max_entry_climb(&self) -> usize;
+
+  fn max_entries_per_edge(&self) -> Option<usize>;
+
+  fn target_inclusion_filter(&self) -> &Option<String>;
+
+  fn target_exclusion_filter(&self) -> &Option<String>;
+
+  fn target_spec(&self) -> &Option<TargetSpec>;
+
+  fn use_regex_filter(&self) -> bool;
+
+  fn max_avg_num_blocks(&self) -> usize;
+
+  fn max_edges_per_target(&self) -> Option<usize>;
+
+  fn sampling_stratum(&self) -> SamplingStratum;
+
+  fn relatedness_policies(&self) -> &Vec<RelatednessPolicy>;
+
+  fn relatedness_prefix_length(&self) -> usize;
+
+  fn dump_reduction_report(&self) -> bool;
+}
@@ -0,0 +1,161 @@
+use llir::values::*;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use crate::utils::*;
+
+use super::options::*;
+
+/// Policy deciding whether a function called inside of a slice is related to
+/// the target function. Functions that cannot reach any related function are
+/// dropped during slice reduction
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RelatednessPolicy {
+  /// The names share a prefix of the configured length
+  NamePrefix,
+
+  /// The signatures share a struct type
+  SignatureStructs,
+
+  /// The signatures or the instructions of the bodies share a struct type
+  BodyStructs,
+
+  /// The function is called on a value flowing into or out of the target call
+  /// inside of the caller
+  DataFlow,
+
+  /// The function is defined in the same source file as the target (or as
+  /// the caller when the target is only declared)
+  SameFile,
+}
+
+impl FromStr for RelatednessPolicy {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "name-prefix" => Ok(Self::NamePrefix),
+      "signature-structs" => Ok(Self::SignatureStructs),
+      "body-structs" => Ok(Self::BodyStructs),
+      "data-flow" => Ok(Self::DataFlow),
+      "same-file" => Ok(Self::SameFile),
+      _ => Err(format!("Unknown relatedness policy {}", s)),
+    }
+  }
+}
+
+pub struct RelatednessChecker<'ctx> {
+  target: Function<'ctx>,
+  policies: Vec<RelatednessPolicy>,
+  prefix_length: usize,
+  target_struct_names: HashSet<String>,
+  target_body_struct_names: HashSet<String>,
+  target_file: Option<String>,
+  data_flow_callees: HashSet<Function<'ctx>>,
+}
+
+impl<'ctx> RelatednessChecker<'ctx> {
+  pub fn new(instr: CallInstruction<'ctx>, target: Function<'ctx>, options: &impl SlicerOptions) -> Self {
+    let policies = options.relatedness_policies().clone();
+    let uses = |policy| policies.contains(&policy);
+    let target_struct_names = if uses(RelatednessPolicy::SignatureStructs) {
+      target.used_struct_names()
+    } else {
+      HashSet::new()
+    };
+    let target_body_struct_names = if uses(RelatednessPolicy::BodyStructs) {
+      target.used_body_struct_names()
+    } else {
+      HashSet::new()
+    };
+    let target_file = target.filename().or_else(|| instr.parent_function().filename());
+    let data_flow_callees = if uses(RelatednessPolicy::DataFlow) {
+      data_flow_callees(instr)
+    } else {
+      HashSet::new()
+    };
+    Self {
+      target,
+      policies,
+      prefix_length: options.relatedness_prefix_length(),
+      target_struct_names,
+      target_body_struct_names,
+      target_file,
+      data_flow_callees,
+    }
+  }
+
+  /// The first policy under which the function is related to the target
+  pub fn related_by(&self, func: &Function<'ctx>) -> Option<RelatednessPolicy> {
+    self
+      .policies
+      .iter()
+      .find(|policy| self.is_related(func, **policy))
+      .cloned()
+  }
+
+  fn is_related(&self, func: &Function<'ctx>, policy: RelatednessPolicy) -> bool {
+    match policy {
+      RelatednessPolicy::NamePrefix => {
+        let prefix = |f: &Function<'ctx>| f.simp_name().chars().take(self.prefix_length).collect::<String>();
+        prefix(func) == prefix(&self.target)
+      }
+      RelatednessPolicy::SignatureStructs => !func.used_struct_names().is_disjoint(&self.target_struct_names),
+      RelatednessPolicy::BodyStructs => !func
+        .used_body_struct_names()
+        .is_disjoint(&self.target_body_struct_names),
+      RelatednessPolicy::DataFlow => self.data_flow_callees.contains(func),
+      RelatednessPolicy::SameFile => match (func.filename(), &self.target_file) {
+        (Some(f1), Some(f2)) => &f1 == f2,
+        _ => false,
+      },
+    }
+  }
+}
+
+/// Functions called inside of the caller on values that flow into the target
+/// arguments, or that are derived from the target arguments or result
+fn data_flow_callees<'ctx>(instr: CallInstruction<'ctx>) -> HashSet<Function<'ctx>> {
+  let mut flows = HashSet::new();
+
+  // Values flowing into the arguments of the target call
+  let mut fringe = instr.arguments();
+  while let Some(operand) = fringe.pop() {
+    if let Operand::Instruction(i) = operand {
+      if flows.insert(i) {
+        fringe.extend(i.operands());
+      }
+    }
+  }
+
+  // Values derived from the arguments or the result of the target call
+  flows.insert(Instruction::Call(instr));
+  let flows = forward_data_flow(instr.parent_function(), flows);
+
+  flows
+    .into_iter()
+    .filter_map(|i| match i {
+      Instruction::Call(call) if call != instr => call.callee_function(),
+      _ => None,
+    })
+    .collect()
+}
+
+/// Why a function inside of a slice is kept or dropped by the reduction
+#[derive(Debug, Clone, Serialize)]
+pub struct ReductionDecision {
+  pub kept: bool,
+
+  /// The name of the related function reachable from the kept function
+  pub related_function: Option<String>,
+
+  /// The policy relating `related_function` to the target
+  pub policy: Option<RelatednessPolicy>,
+}
+
+/// Map from function name to the reduction decision of that function. Names
+/// are not simplified, so that overloads and `static` functions of different
+/// files sharing a simplified name are told apart
+pub type ReductionReport = HashMap<String, ReductionDecision>;
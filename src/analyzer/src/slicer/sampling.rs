@@ -0,0 +1,59 @@
+use petgraph::graph::*;
+use rand::{rngs::StdRng, SeedableRng};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::call_graph::*;
+use crate::utils::*;
+
+use super::options::*;
+
+/// How call sites of a target are grouped when sampled
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SamplingStratum {
+  /// Source directory of the caller
+  Directory,
+
+  /// Source file of the caller
+  File,
+}
+
+impl FromStr for SamplingStratum {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "directory" => Ok(Self::Directory),
+      "file" => Ok(Self::File),
+      _ => Err(format!("Unknown sampling stratum {}", s)),
+    }
+  }
+}
+
+pub trait CallEdgeSampler {
+  /// Sample the call edges of a target when exceeding `max_edges_per_target`.
+  /// Every returned edge comes with its sampling weight
+  fn sample_call_edges(&self, edges: &[EdgeIndex], options: &impl SlicerOptions) -> Vec<(EdgeIndex, f64)>;
+}
+
+impl<'ctx> CallEdgeSampler for CallGraph<'ctx> {
+  fn sample_call_edges(&self, edges: &[EdgeIndex], options: &impl SlicerOptions) -> Vec<(EdgeIndex, f64)> {
+    match options.max_edges_per_target() {
+      Some(max_edges) if edges.len() > max_edges => {
+        let mut rng = StdRng::seed_from_u64(options.seed());
+        let stratum_of = |edge_id: &EdgeIndex| -> Option<String> {
+          let (caller_id, _) = self.graph.edge_endpoints(*edge_id)?;
+          let filename = self.graph[caller_id].filename()?;
+          match options.sampling_stratum() {
+            SamplingStratum::File => Some(filename),
+            SamplingStratum::Directory => Path::new(&filename)
+              .parent()
+              .map(|dir| dir.to_string_lossy().to_string()),
+          }
+        };
+        stratified_sample(edges.to_vec(), stratum_of, max_edges, &mut rng)
+      }
+      _ => edges.iter().map(|edge_id| (*edge_id, 1.0)).collect(),
+    }
+  }
+}
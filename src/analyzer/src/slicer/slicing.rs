@@ -1,5 +1,6 @@
 use llir::values::*;
 use petgraph::{graph::*, visit::*, Direction};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
 use rayon::prelude::*;
 use regex::Regex;
 use serde_json::json;
@@ -10,23 +11,14 @@ use std::path::PathBuf;
 
 use crate::call_graph::*;
 use crate::options::*;
+use crate::target_spec::*;
 use crate::utils::*;
 
-pub trait SlicerOptions: GeneralOptions + Send + Sync {
-  fn no_reduce_slice(&self) -> bool;
-
-  fn slice_depth(&self) -> usize;
-
-  fn entry_filter(&self) -> &Option<String>;
-
-  fn target_inclusion_filter(&self) -> &Option<String>;
-
-  fn target_exclusion_filter(&self) -> &Option<String>;
-
-  fn use_regex_filter(&self) -> bool;
-
-  fn max_avg_num_blocks(&self) -> usize;
-}
+use super::depth::*;
+use super::entry::*;
+use super::options::*;
+use super::relatedness::*;
+use super::sampling::*;
 
 #[derive(Clone)]
 pub struct Slice<'ctx> {
@@ -35,6 +27,14 @@ pub struct Slice<'ctx> {
   pub callee: Function<'ctx>,
   pub instr: CallInstruction<'ctx>,
   pub functions: HashSet<Function<'ctx>>,
+  pub reduction_report: Option<ReductionReport>,
+
+  /// Number of call sites of the target represented by this slice's call site
+  /// when the call sites are sampled
+  pub sampling_weight: f64,
+
+  /// The depth the slice is generated with
+  pub depth: usize,
 }
 
 impl<'ctx> Slice<'ctx> {
@@ -43,13 +43,20 @@ impl<'ctx> Slice<'ctx> {
   }
 
   pub fn to_json(&self) -> serde_json::Value {
-    json!({
+    let mut slice_json = json!({
       "entry": self.entry.simp_name(),
       "caller": self.caller.simp_name(),
       "callee": self.callee.simp_name(),
       "instr": self.instr.debug_loc_string(),
+      "instr_loc": self.instr.debug_location(),
       "functions": self.functions.iter().map(|f| f.simp_name()).collect::<Vec<_>>(),
-    })
+      "sampling_weight": self.sampling_weight,
+      "depth": self.depth,
+    });
+    if let Some(report) = &self.reduction_report {
+      slice_json["reduction_report"] = json!(report);
+    }
+    slice_json
   }
 
   pub fn target_function_name(&self) -> String {
@@ -119,7 +126,10 @@ impl TargetEdgesMapTrait for TargetEdgesMap {
     for callee_id in call_graph.graph.node_indices() {
       let func = call_graph.graph[callee_id];
       let func_name = func.simp_name();
-      let include_from_inclusion = inclusion_filter.matches(func_name.as_str());
+      let include_from_inclusion = match options.target_spec() {
+        Some(spec) => spec.matches(&func),
+        None => inclusion_filter.matches(func_name.as_str()),
+      };
       let include = if !include_from_inclusion {
         false
       } else {
@@ -145,6 +155,7 @@ pub trait TargetSlicesMapTrait<'ctx>: Sized {
   fn from_target_edges_map(
     target_edges_map: &TargetEdgesMap,
     call_graph: &CallGraph<'ctx>,
+    entry_matcher: &EntryMatcher,
     options: &impl SlicerOptions,
   ) -> Self;
 
@@ -157,11 +168,26 @@ impl<'ctx> TargetSlicesMapTrait<'ctx> for TargetSlicesMap<'ctx> {
   fn from_target_edges_map(
     target_edges_map: &TargetEdgesMap,
     call_graph: &CallGraph<'ctx>,
+    entry_matcher: &EntryMatcher,
     options: &impl SlicerOptions,
   ) -> Self {
     let mut result = HashMap::new();
     for (target, edges) in target_edges_map {
-      let slices = call_graph.slices_of_call_edges(&edges[..], options);
+      let overrides = match options.target_spec() {
+        Some(spec) => spec.overrides_of_target(
+          edges
+            .iter()
+            .filter_map(|edge_id| call_graph.graph.edge_endpoints(*edge_id))
+            .map(|(_, callee_id)| call_graph.graph[callee_id]),
+        ),
+        None => None,
+      };
+      let slices = match overrides {
+        Some(overrides) => {
+          call_graph.slices_of_target(&edges[..], entry_matcher, &TargetOptions::new(options, overrides))
+        }
+        None => call_graph.slices_of_target(&edges[..], entry_matcher, options),
+      };
       result.insert(target.clone(), slices);
     }
     result
@@ -207,44 +233,99 @@ impl TargetNumSlicesMapTrait for TargetNumSlicesMap {
 }
 
 pub trait Slicer<'ctx> {
-  fn reduce_slice(&self, target_id: NodeIndex, functions: HashSet<NodeIndex>, depth: usize) -> HashSet<NodeIndex>;
+  fn reduce_slice(
+    &self,
+    edge_id: EdgeIndex,
+    functions: HashSet<NodeIndex>,
+    depth: usize,
+    options: &impl SlicerOptions,
+  ) -> (HashSet<NodeIndex>, ReductionReport);
 
-  fn find_entries(&self, edge_id: EdgeIndex, options: &impl SlicerOptions) -> Vec<NodeIndex>;
+  fn find_entries(
+    &self,
+    edge_id: EdgeIndex,
+    depth: usize,
+    entry_matcher: &EntryMatcher,
+    options: &impl SlicerOptions,
+  ) -> Vec<NodeIndex>;
 
-  fn slice_of_entry(&self, entry_id: NodeIndex, edge_id: EdgeIndex, options: &impl SlicerOptions) -> Slice<'ctx>;
+  fn slice_of_entry(
+    &self,
+    entry_id: NodeIndex,
+    edge_id: EdgeIndex,
+    depth: usize,
+    options: &impl SlicerOptions,
+  ) -> Slice<'ctx>;
 
-  fn slices_of_call_edge(&self, edge_id: EdgeIndex, options: &impl SlicerOptions) -> Vec<Slice<'ctx>>;
+  fn slices_of_call_edge(
+    &self,
+    edge_id: EdgeIndex,
+    entry_matcher: &EntryMatcher,
+    options: &impl SlicerOptions,
+  ) -> Vec<Slice<'ctx>>;
 
-  fn slices_of_call_edges(&self, edges: &[EdgeIndex], options: &impl SlicerOptions) -> Vec<Slice<'ctx>>;
+  fn slices_of_call_edges(
+    &self,
+    edges: &[EdgeIndex],
+    entry_matcher: &EntryMatcher,
+    options: &impl SlicerOptions,
+  ) -> Vec<Slice<'ctx>>;
+
+  fn slices_of_target(
+    &self,
+    edges: &[EdgeIndex],
+    entry_matcher: &EntryMatcher,
+    options: &impl SlicerOptions,
+  ) -> Vec<Slice<'ctx>>;
 }
 
 impl<'ctx> Slicer<'ctx> for CallGraph<'ctx> {
-  fn reduce_slice(&self, target_id: NodeIndex, functions: HashSet<NodeIndex>, depth: usize) -> HashSet<NodeIndex> {
-    let target = self.graph[target_id];
+  fn reduce_slice(
+    &self,
+    edge_id: EdgeIndex,
+    functions: HashSet<NodeIndex>,
+    depth: usize,
+    options: &impl SlicerOptions,
+  ) -> (HashSet<NodeIndex>, ReductionReport) {
+    let (_, target_id) = self.graph.edge_endpoints(edge_id).unwrap();
+    let checker = RelatednessChecker::new(self.graph[edge_id], self.graph[target_id], options);
     let all_presented_funcs: HashSet<_> = functions
       .iter()
       .map(|f_id| -> Vec<NodeIndex> { self.graph.neighbors(*f_id).collect() })
       .flatten()
       .collect();
-    let related_funcs: HashSet<_> = all_presented_funcs
-      .iter()
-      .filter(|f_id| directly_related(&self.graph[**f_id], &target))
+    let related_funcs: Vec<_> = all_presented_funcs
+      .into_iter()
+      .filter_map(|f_id| checker.related_by(&self.graph[f_id]).map(|policy| (f_id, policy)))
       .collect();
-    functions
-      .iter()
-      .filter(|f_id| {
-        for rf_id in related_funcs.iter() {
-          for _ in petgraph::algo::all_simple_paths::<Vec<_>, _>(&self.graph, **f_id, **rf_id, 0, Some(depth * 2)) {
-            return true;
-          }
-        }
-        return false;
-      })
-      .cloned()
-      .collect()
+    let mut reduced_functions = HashSet::new();
+    let mut report = ReductionReport::new();
+    for f_id in functions {
+      let reached = related_funcs.iter().find(|(rf_id, _)| {
+        petgraph::algo::all_simple_paths::<Vec<_>, _>(&self.graph, f_id, *rf_id, 0, Some(depth * 2))
+          .next()
+          .is_some()
+      });
+      let decision = ReductionDecision {
+        kept: reached.is_some(),
+        related_function: reached.map(|(rf_id, _)| self.graph[*rf_id].name()),
+        policy: reached.map(|(_, policy)| *policy),
+      };
+      report.insert(self.graph[f_id].name(), decision);
+      if reached.is_some() {
+        reduced_functions.insert(f_id);
+      }
+    }
+    (reduced_functions, report)
   }
 
-  fn find_entries(&self, edge_id: EdgeIndex, options: &impl SlicerOptions) -> Vec<NodeIndex> {
+  fn find_entries(
+    &self,
+    edge_id: EdgeIndex,
+    depth: usize,
+    entry_matcher: &EntryMatcher,
+    options: &impl SlicerOptions,
+  ) -> Vec<NodeIndex> {
     let entry_location_filter = match options.entry_filter() {
       Some(filter) => Some(
         Regex::new(filter.as_str())
@@ -257,18 +338,30 @@ impl<'ctx> Slicer<'ctx> for CallGraph<'ctx> {
     match self.graph.edge_endpoints(edge_id) {
       Some((func_id, _)) => {
         let mut fringe = Vec::new();
-        fringe.push((func_id, options.slice_depth()));
+        let mut visited = HashSet::new();
+        fringe.push((func_id, depth, 0));
         while !fringe.is_empty() {
-          let (func_id, depth) = fringe.pop().unwrap();
+          let (func_id, depth, climb) = fringe.pop().unwrap();
+          let func = self.graph[func_id];
           if depth == 0 {
+            if entry_matcher.matches(&func) {
+              result.insert(func_id);
+            } else if climb < options.max_entry_climb() && visited.insert(func_id) {
+              // Keep climbing beyond the slice depth until reaching a matching entry
+              for caller_id in self.graph.neighbors_directed(func_id, Direction::Incoming) {
+                fringe.push((caller_id, 0, climb + 1));
+              }
+            }
+          } else if options.stop_at_entries() && entry_matcher.stops_at(&func) {
+            // Stop climbing at a semantically meaningful entry
             result.insert(func_id);
           } else {
             let mut contains_parent = false;
             for caller_id in self.graph.neighbors_directed(func_id, Direction::Incoming) {
               contains_parent = true;
-              fringe.push((caller_id, depth - 1));
+              fringe.push((caller_id, depth - 1, 0));
             }
-            if !contains_parent {
+            if !contains_parent && entry_matcher.matches(&func) {
               result.insert(func_id);
             }
           }
@@ -276,7 +369,7 @@ impl<'ctx> Slicer<'ctx> for CallGraph<'ctx> {
       }
       None => (),
     }
-    result
+    let mut entries = result
       .into_iter()
       .filter(|func_id| match &entry_location_filter {
         Some(regex) => {
@@ -288,10 +381,31 @@ impl<'ctx> Slicer<'ctx> for CallGraph<'ctx> {
         }
         None => true,
       })
-      .collect()
+      .collect::<Vec<_>>();
+
+    // Sample the entries deterministically when there are too many of them
+    match options.max_entries_per_edge() {
+      Some(max_entries) if entries.len() > max_entries => {
+        entries.sort();
+        let mut rng = StdRng::seed_from_u64(options.seed() ^ edge_id.index() as u64);
+        let mut sampled = entries
+          .choose_multiple(&mut rng, max_entries)
+          .cloned()
+          .collect::<Vec<_>>();
+        sampled.sort();
+        sampled
+      }
+      _ => entries,
+    }
   }
 
-  fn slice_of_entry(&self, entry_id: NodeIndex, edge_id: EdgeIndex, options: &impl SlicerOptions) -> Slice<'ctx> {
+  fn slice_of_entry(
+    &self,
+    entry_id: NodeIndex,
+    edge_id: EdgeIndex,
+    depth: usize,
+    options: &impl SlicerOptions,
+  ) -> Slice<'ctx> {
     // Get basic informations
     let entry = self.graph[entry_id];
     let instr = self.graph[edge_id];
@@ -301,7 +415,7 @@ impl<'ctx> Slicer<'ctx> for CallGraph<'ctx> {
     };
 
     // Get included functions
-    let mut fringe = vec![(entry_id, options.slice_depth() * 2)];
+    let mut fringe = vec![(entry_id, depth * 2)];
     let mut visited = HashSet::new();
     let mut function_ids = HashSet::new();
     while !fringe.is_empty() {
@@ -325,10 +439,15 @@ impl<'ctx> Slicer<'ctx> for CallGraph<'ctx> {
     }
 
     // Reduced function boundary
-    let function_ids = if options.no_reduce_slice() {
-      function_ids
+    let (function_ids, reduction_report) = if options.no_reduce_slice() {
+      (function_ids, None)
     } else {
-      self.reduce_slice(callee_id, function_ids, options.slice_depth())
+      let (function_ids, report) = self.reduce_slice(edge_id, function_ids, depth, options);
+      if options.dump_reduction_report() {
+        (function_ids, Some(report))
+      } else {
+        (function_ids, None)
+      }
     };
 
     // Generate slice
@@ -339,15 +458,28 @@ impl<'ctx> Slicer<'ctx> for CallGraph<'ctx> {
       instr,
       entry,
       functions,
+      reduction_report,
+      sampling_weight: 1.0,
+      depth,
     }
   }
 
-  fn slices_of_call_edge(&self, edge_id: EdgeIndex, options: &impl SlicerOptions) -> Vec<Slice<'ctx>> {
-    let entry_ids = self.find_entries(edge_id, options);
+  fn slices_of_call_edge(
+    &self,
+    edge_id: EdgeIndex,
+    entry_matcher: &EntryMatcher,
+    options: &impl SlicerOptions,
+  ) -> Vec<Slice<'ctx>> {
+    let depth = if options.adaptive_slice_depth() {
+      self.adaptive_slice_depth(edge_id, options)
+    } else {
+      options.slice_depth()
+    };
+    let entry_ids = self.find_entries(edge_id, depth, entry_matcher, options);
     entry_ids
       .into_iter()
       .filter_map(|entry_id| {
-        let slice = self.slice_of_entry(entry_id, edge_id, options);
+        let slice = self.slice_of_entry(entry_id, edge_id, depth, options);
         if needs_include_slice(&slice, options) {
           Some(slice)
         } else {
@@ -357,14 +489,42 @@ impl<'ctx> Slicer<'ctx> for CallGraph<'ctx> {
       .collect()
   }
 
-  fn slices_of_call_edges(&self, edges: &[EdgeIndex], options: &impl SlicerOptions) -> Vec<Slice<'ctx>> {
-    let f = |edge_id: &EdgeIndex| -> Vec<Slice<'ctx>> { self.slices_of_call_edge(edge_id.clone(), options) };
+  fn slices_of_call_edges(
+    &self,
+    edges: &[EdgeIndex],
+    entry_matcher: &EntryMatcher,
+    options: &impl SlicerOptions,
+  ) -> Vec<Slice<'ctx>> {
+    let f =
+      |edge_id: &EdgeIndex| -> Vec<Slice<'ctx>> { self.slices_of_call_edge(edge_id.clone(), entry_matcher, options) };
     if options.use_serial() {
       edges.iter().map(f).flatten().collect()
     } else {
       edges.par_iter().map(f).flatten().collect()
     }
   }
+
+  fn slices_of_target(
+    &self,
+    edges: &[EdgeIndex],
+    entry_matcher: &EntryMatcher,
+    options: &impl SlicerOptions,
+  ) -> Vec<Slice<'ctx>> {
+    let sampled_edges = self.sample_call_edges(edges, options);
+    let weights: HashMap<_, _> = sampled_edges
+      .iter()
+      .map(|(edge_id, weight)| (self.graph[*edge_id], *weight))
+      .collect();
+    let edges = sampled_edges
+      .into_iter()
+      .map(|(edge_id, _)| edge_id)
+      .collect::<Vec<_>>();
+    let mut slices = self.slices_of_call_edges(&edges[..], entry_matcher, options);
+    for slice in &mut slices {
+      slice.sampling_weight = weights[&slice.instr];
+    }
+    slices
+  }
 }
 
 fn needs_include_slice<'ctx>(slice: &Slice<'ctx>, options: &impl SlicerOptions) -> bool {
@@ -372,14 +532,3 @@ fn needs_include_slice<'ctx>(slice: &Slice<'ctx>, options: &impl SlicerOptions)
   let avg_num_blocks = sum_of_blocks / slice.functions.len();
   avg_num_blocks < options.max_avg_num_blocks()
 }
-
-fn directly_related<'ctx>(f1: &Function<'ctx>, f2: &Function<'ctx>) -> bool {
-  // Has similar prefix
-  if f1.simp_name().chars().nth(0) == f2.simp_name().chars().nth(0) {
-    true
-  } else {
-    let structs_used_by_f1 = f1.used_struct_names();
-    let structs_used_by_f2 = f2.used_struct_names();
-    !structs_used_by_f1.is_disjoint(&structs_used_by_f2)
-  }
-}
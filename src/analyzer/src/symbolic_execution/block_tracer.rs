@@ -1,11 +1,12 @@
 use llir::values::*;
 use petgraph::graph::{DiGraph, NodeIndex};
 use std::collections::HashMap;
+use std::rc::Rc;
 
 use crate::call_graph::*;
 use crate::semantics::*;
 use crate::slicer::*;
-use crate::utils;
+use crate::utils::{self, BlockUtil};
 
 /// The block trace inside a function.
 ///
@@ -14,70 +15,77 @@ use crate::utils;
 /// - block_traces: The list of traces that can go from starting block to the
 ///   block that contains the target call
 /// - call: The final Call Instruction that leads us to the next function
-///   or the target function call
+///   or the target function call. `None` for the traces after the target call,
+///   which end at an exit of the function
 #[derive(Debug)]
 pub struct CompositeFunctionBlockTraces<'ctx> {
   function: Function<'ctx>,
   block_traces: Vec<Vec<Block<'ctx>>>,
-  call_instr: CallInstruction<'ctx>,
+  call_instr: Option<CallInstruction<'ctx>>,
 }
 
 /// A block trace is a list of FunctionBlockTrace. When finally
 pub type CompositeBlockTrace<'ctx> = Vec<CompositeFunctionBlockTraces<'ctx>>;
 
-/// One block trace inside a function leading to the call instruction
+/// One block trace inside a function leading to the call instruction, or to
+/// an exit of the function when there is no call instruction
 #[derive(Debug, Clone)]
 pub struct FunctionBlockTrace<'ctx> {
   pub function: Function<'ctx>,
   pub block_trace: Vec<Block<'ctx>>,
-  pub call_instr: CallInstruction<'ctx>,
+  pub call_instr: Option<CallInstruction<'ctx>>,
 }
 
 /// Block trace is an array of function block trace
 pub type BlockTrace<'ctx> = Vec<FunctionBlockTrace<'ctx>>;
 
 pub trait GenerateBlockTraceTrait<'ctx> {
-  fn block_traces(&self) -> Vec<BlockTrace<'ctx>>;
+  fn into_block_traces(self) -> CompositeBlockTraceIterator<'ctx>;
 }
 
 impl<'ctx> GenerateBlockTraceTrait<'ctx> for CompositeBlockTrace<'ctx> {
-  fn block_traces(&self) -> Vec<BlockTrace<'ctx>> {
-    if self.len() == 0 {
-      vec![]
-    } else {
-      let func_num_block_traces: Vec<usize> = self
-        .iter()
-        .map(|func_blk_trace| func_blk_trace.block_traces.len())
-        .collect();
-      let num_block_traces = func_num_block_traces.iter().product();
-      let mut block_traces = Vec::with_capacity(num_block_traces);
-      let cartesian = utils::cartesian(&func_num_block_traces);
-      for indices in cartesian {
-        let block_trace = indices
-          .iter()
-          .enumerate()
-          .filter_map(|(i, j)| {
-            if i < self.len() && *j < self[i].block_traces.len() {
-              Some(FunctionBlockTrace {
-                function: self[i].function,
-                block_trace: self[i].block_traces[*j].clone(),
-                call_instr: self[i].call_instr,
-              })
-            } else {
-              None
-            }
-          })
-          .collect();
-        block_traces.push(block_trace);
-      }
-      block_traces
+  fn into_block_traces(self) -> CompositeBlockTraceIterator<'ctx> {
+    let func_num_block_traces = self
+      .iter()
+      .map(|func_blk_trace| func_blk_trace.block_traces.len())
+      .collect();
+    CompositeBlockTraceIterator {
+      indices: utils::DiagonalCartesianIterator::new(func_num_block_traces),
+      composite: self,
     }
   }
 }
 
+/// Lazily enumerate the block traces of a composite block trace. Every block
+/// trace of every function appears in the first few block traces, so that
+/// taking only a prefix still covers all the per-function traces
+#[derive(Debug)]
+pub struct CompositeBlockTraceIterator<'ctx> {
+  composite: CompositeBlockTrace<'ctx>,
+  indices: utils::DiagonalCartesianIterator,
+}
+
+impl<'ctx> Iterator for CompositeBlockTraceIterator<'ctx> {
+  type Item = BlockTrace<'ctx>;
+
+  fn next(&mut self) -> Option<BlockTrace<'ctx>> {
+    let indices = self.indices.next()?;
+    let block_trace = indices
+      .iter()
+      .zip(self.composite.iter())
+      .map(|(j, func_blk_traces)| FunctionBlockTrace {
+        function: func_blk_traces.function,
+        block_trace: func_blk_traces.block_traces[*j].clone(),
+        call_instr: func_blk_traces.call_instr,
+      })
+      .collect();
+    Some(block_trace)
+  }
+}
+
 #[derive(Clone, Debug)]
 pub struct BlockTraceIterator<'ctx> {
-  pub block_trace: BlockTrace<'ctx>,
+  pub block_trace: Rc<BlockTrace<'ctx>>,
   pub function_id: usize,
   pub block_id: usize,
 }
@@ -85,7 +93,7 @@ pub struct BlockTraceIterator<'ctx> {
 impl<'ctx> BlockTraceIterator<'ctx> {
   pub fn empty() -> Self {
     Self {
-      block_trace: vec![],
+      block_trace: Rc::new(vec![]),
       function_id: 0,
       block_id: 0,
     }
@@ -93,7 +101,7 @@ impl<'ctx> BlockTraceIterator<'ctx> {
 
   pub fn from_block_trace(block_trace: BlockTrace<'ctx>) -> Self {
     Self {
-      block_trace,
+      block_trace: Rc::new(block_trace),
       function_id: 0,
       block_id: 0,
     }
@@ -101,7 +109,24 @@ impl<'ctx> BlockTraceIterator<'ctx> {
 
   pub fn visit_call(&mut self, instr: CallInstruction<'ctx>) -> bool {
     if self.function_id < self.block_trace.len() {
-      if self.block_trace[self.function_id].call_instr == instr {
+      if self.block_trace[self.function_id].call_instr == Some(instr) {
+        self.function_id += 1;
+        self.block_id = 0;
+        true
+      } else {
+        false
+      }
+    } else {
+      false
+    }
+  }
+
+  /// Returning from a function whose post-target trace is being followed
+  /// moves on to the trace of the function up the call stack
+  pub fn visit_return(&mut self, func: Function<'ctx>) -> bool {
+    if self.function_id < self.block_trace.len() {
+      let func_block_trace = &self.block_trace[self.function_id];
+      if func_block_trace.call_instr.is_none() && func_block_trace.function == func {
         self.function_id += 1;
         self.block_id = 0;
         true
@@ -157,20 +182,83 @@ pub struct BlockGraph<'ctx> {
 }
 
 impl<'ctx> BlockGraph<'ctx> {
-  pub fn find_paths(&self, entry: Block<'ctx>, target: Block<'ctx>, limit: usize) -> Vec<Vec<Block<'ctx>>> {
-    petgraph::algo::all_simple_paths(&self.graph, self.block_id_map[&entry], self.block_id_map[&target], 0, None)
-      .take(limit)
-      .map(|path: Vec<_>| {
-        path.into_iter().map(|ni| self.graph[ni]).collect()
-      })
-      .collect()
+  /// Find at most `limit` paths from `entry` to `target`. Each loop can be
+  /// unrolled `unroll_bound` times, i.e. a block can appear `unroll_bound + 1`
+  /// times in a path. The paths stop at the first arrival to `target`
+  pub fn find_paths(
+    &self,
+    entry: Block<'ctx>,
+    target: Block<'ctx>,
+    limit: usize,
+    unroll_bound: usize,
+  ) -> Vec<Vec<Block<'ctx>>> {
+    let (from, to) = (self.block_id_map[&entry], self.block_id_map[&target]);
+    if unroll_bound == 0 {
+      petgraph::algo::all_simple_paths(&self.graph, from, to, 0, None)
+        .take(limit)
+        .map(|path: Vec<_>| {
+          path.into_iter().map(|ni| self.graph[ni]).collect()
+        })
+        .collect()
+    } else {
+      let mut paths = vec![];
+      let mut path = vec![from];
+      let mut visit_counts = HashMap::new();
+      visit_counts.insert(from, 1);
+      let mut stack = vec![self.graph.neighbors(from).collect::<Vec<_>>()];
+      while let Some(children) = stack.last_mut() {
+        if paths.len() >= limit {
+          break;
+        }
+        match children.pop() {
+          Some(child) if child == to => {
+            let blocks = path.iter().chain(std::iter::once(&to)).map(|ni| self.graph[*ni]);
+            paths.push(blocks.collect());
+          }
+          Some(child) => {
+            let count = visit_counts.entry(child).or_insert(0);
+            if *count <= unroll_bound {
+              *count += 1;
+              path.push(child);
+              stack.push(self.graph.neighbors(child).collect());
+            }
+          }
+          None => {
+            stack.pop();
+            if let Some(node) = path.pop() {
+              *visit_counts.get_mut(&node).unwrap() -= 1;
+            }
+          }
+        }
+      }
+      paths
+    }
   }
 }
 
 pub trait FunctionBlockGraphTrait<'ctx> {
   fn block_graph(&self) -> BlockGraph<'ctx>;
 
-  fn block_traces_to_instr(&self, instr: Instruction<'ctx>, max_traces: usize) -> Vec<Vec<Block<'ctx>>>;
+  fn block_traces_to_instr(
+    &self,
+    instr: Instruction<'ctx>,
+    max_traces: usize,
+    unroll_bound: usize,
+    cache: &mut BlockGraphCache<'ctx>,
+  ) -> Vec<Vec<Block<'ctx>>>;
+
+  /// Block traces from the block of the instruction to the exits (returning
+  /// blocks) of the function. Blocks ending with `unreachable`, e.g. after a
+  /// call that never returns, are only used when no returning block can be
+  /// reached. The traces to different exits are interleaved so that every exit
+  /// comes before the second trace of any exit
+  fn block_traces_from_instr_to_exits(
+    &self,
+    instr: Instruction<'ctx>,
+    max_traces: usize,
+    unroll_bound: usize,
+    cache: &mut BlockGraphCache<'ctx>,
+  ) -> Vec<Vec<Block<'ctx>>>;
 }
 
 impl<'ctx> FunctionBlockGraphTrait<'ctx> for Function<'ctx> {
@@ -183,7 +271,7 @@ impl<'ctx> FunctionBlockGraphTrait<'ctx> for Function<'ctx> {
         .or_insert_with(|| graph.add_node(block))
         .clone();
       let terminator = block.last_instruction().unwrap();
-      let next_blocks = block.destination_blocks();
+      let next_blocks = block.successor_blocks();
       for next_block in next_blocks {
         let next_block_id = block_id_map
           .entry(next_block)
@@ -195,42 +283,146 @@ impl<'ctx> FunctionBlockGraphTrait<'ctx> for Function<'ctx> {
     BlockGraph { graph, block_id_map }
   }
 
-  fn block_traces_to_instr(&self, instr: Instruction<'ctx>, max_traces: usize) -> Vec<Vec<Block<'ctx>>> {
+  fn block_traces_to_instr(
+    &self,
+    instr: Instruction<'ctx>,
+    max_traces: usize,
+    unroll_bound: usize,
+    cache: &mut BlockGraphCache<'ctx>,
+  ) -> Vec<Vec<Block<'ctx>>> {
     let entry_block = self.first_block().unwrap();
     if entry_block == instr.parent_block() {
       vec![vec![entry_block]]
     } else {
-      let block_graph = self.block_graph();
-      block_graph.find_paths(entry_block, instr.parent_block(), max_traces)
+      let block_graph = cache.block_graph_of(*self);
+      block_graph.find_paths(entry_block, instr.parent_block(), max_traces, unroll_bound)
     }
   }
+
+  fn block_traces_from_instr_to_exits(
+    &self,
+    instr: Instruction<'ctx>,
+    max_traces: usize,
+    unroll_bound: usize,
+    cache: &mut BlockGraphCache<'ctx>,
+  ) -> Vec<Vec<Block<'ctx>>> {
+    let begin_block = instr.parent_block();
+    let block_graph = cache.block_graph_of(*self);
+    let traces_to = |exit_blocks: &Vec<Block<'ctx>>| -> Vec<Vec<Vec<Block<'ctx>>>> {
+      exit_blocks
+        .iter()
+        .map(|exit_block| {
+          if *exit_block == begin_block {
+            vec![vec![begin_block]]
+          } else {
+            block_graph.find_paths(begin_block, *exit_block, max_traces, unroll_bound)
+          }
+        })
+        .collect()
+    };
+    let (returning_exits, other_exits): (Vec<_>, Vec<_>) = self
+      .iter_blocks()
+      .filter(|block| block.successor_blocks().is_empty())
+      .partition(|block| match block.last_instruction() {
+        Some(Instruction::Return(_)) => true,
+        _ => false,
+      });
+    let mut traces_per_exit = traces_to(&returning_exits);
+    if traces_per_exit.iter().all(|traces| traces.is_empty()) {
+      traces_per_exit = traces_to(&other_exits);
+    }
+    let max_num_traces = traces_per_exit.iter().map(|traces| traces.len()).max().unwrap_or(0);
+    (0..max_num_traces)
+      .flat_map(|i| traces_per_exit.iter().filter_map(move |traces| traces.get(i).cloned()))
+      .take(max_traces)
+      .collect()
+  }
+}
+
+/// Block graphs of the functions visited so far, so that a function shared
+/// by multiple call graph paths only builds its block graph once
+pub type BlockGraphCache<'ctx> = HashMap<Function<'ctx>, BlockGraph<'ctx>>;
+
+pub trait BlockGraphCacheTrait<'ctx> {
+  fn block_graph_of(&mut self, func: Function<'ctx>) -> &BlockGraph<'ctx>;
+}
+
+impl<'ctx> BlockGraphCacheTrait<'ctx> for BlockGraphCache<'ctx> {
+  fn block_graph_of(&mut self, func: Function<'ctx>) -> &BlockGraph<'ctx> {
+    self.entry(func).or_insert_with(|| func.block_graph())
+  }
 }
 
 pub trait BlockTracesFromCallGraphPath<'ctx> {
-  fn block_traces(&self, max_traces_per_function: usize) -> Vec<BlockTrace<'ctx>>;
+  /// When `post_target` is set, the block traces continue after the target
+  /// call to the exits of the caller, then of every function up to the entry
+  fn block_traces(
+    &self,
+    max_traces_per_function: usize,
+    unroll_bound: usize,
+    post_target: bool,
+    cache: &mut BlockGraphCache<'ctx>,
+  ) -> CompositeBlockTraceIterator<'ctx>;
 }
 
 impl<'ctx> BlockTracesFromCallGraphPath<'ctx> for CallGraphPath<'ctx> {
-  fn block_traces(&self, max_traces_per_function: usize) -> Vec<BlockTrace<'ctx>> {
+  fn block_traces(
+    &self,
+    max_traces_per_function: usize,
+    unroll_bound: usize,
+    post_target: bool,
+    cache: &mut BlockGraphCache<'ctx>,
+  ) -> CompositeBlockTraceIterator<'ctx> {
     let mut curr_func = self.begin;
     let mut comp_trace = vec![];
     for (call_instr, next_func) in &self.succ {
-      let block_traces = curr_func.block_traces_to_instr(call_instr.as_instruction(), max_traces_per_function);
+      let block_traces = curr_func.block_traces_to_instr(
+        call_instr.as_instruction(),
+        max_traces_per_function,
+        unroll_bound,
+        cache,
+      );
       comp_trace.push(CompositeFunctionBlockTraces {
         function: curr_func,
         block_traces,
-        call_instr: call_instr.clone(),
+        call_instr: Some(call_instr.clone()),
       });
       curr_func = next_func.clone();
     }
-    comp_trace.block_traces()
+    if post_target {
+      let mut post_trace = vec![];
+      for func_traces in comp_trace.iter().rev() {
+        let call_instr = func_traces.call_instr.unwrap();
+        let block_traces = func_traces.function.block_traces_from_instr_to_exits(
+          call_instr.as_instruction(),
+          max_traces_per_function,
+          unroll_bound,
+          cache,
+        );
+        post_trace.push(CompositeFunctionBlockTraces {
+          function: func_traces.function,
+          block_traces,
+          call_instr: None,
+        });
+      }
+      comp_trace.extend(post_trace);
+    }
+    comp_trace.into_block_traces()
   }
 }
 
 pub trait BlockTracesFromSlice<'ctx> {
   fn function_traces(&self, cg: &CallGraph<'ctx>, d: usize) -> Vec<CallGraphPath<'ctx>>;
 
-  fn block_traces(&self, cg: &CallGraph<'ctx>, d: usize, max_traces: usize) -> Vec<BlockTrace<'ctx>>;
+  /// Lazily generate the block traces of the slice, path by path
+  fn block_traces(
+    &self,
+    cg: &CallGraph<'ctx>,
+    d: usize,
+    max_traces: usize,
+    unroll_bound: usize,
+    post_target: bool,
+  ) -> Box<dyn Iterator<Item = BlockTrace<'ctx>> + 'ctx>;
 }
 
 impl<'ctx> BlockTracesFromSlice<'ctx> for Slice<'ctx> {
@@ -261,11 +453,15 @@ impl<'ctx> BlockTracesFromSlice<'ctx> for Slice<'ctx> {
     call_graph: &CallGraph<'ctx>,
     max_func_depth: usize,
     max_traces: usize,
-  ) -> Vec<BlockTrace<'ctx>> {
-    let mut traces = vec![];
-    for func_trace in self.function_traces(call_graph, max_func_depth) {
-      traces.extend(func_trace.block_traces(max_traces));
-    }
-    traces
+    unroll_bound: usize,
+    post_target: bool,
+  ) -> Box<dyn Iterator<Item = BlockTrace<'ctx>> + 'ctx> {
+    let mut cache = BlockGraphCache::new();
+    let func_traces = self.function_traces(call_graph, max_func_depth);
+    Box::new(
+      func_traces
+        .into_iter()
+        .flat_map(move |func_trace| func_trace.block_traces(max_traces, unroll_bound, post_target, &mut cache)),
+    )
   }
 }
@@ -1,38 +1,53 @@
+use serde::Serialize;
 use std::collections::HashMap;
 
 use crate::semantics::rced::*;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Constraint {
   pub cond: Comparison,
+
+  /// Whether the then branch is taken, i.e. the comparison holds
   pub branch: bool,
+
+  /// Source location of the branch
+  pub loc: String,
+
+  /// Index of the branch node in the trace
+  #[serde(rename = "node")]
+  pub node_id: usize,
 }
 
-pub type Constraints = Vec<Constraint>;
+pub type Constraints = im_rc::Vector<Constraint>;
 
 pub trait ConstraintsTrait {
-  fn sat(&self) -> bool;
+  /// The path condition as a SMT-LIB2 query. Constraints that cannot be
+  /// encoded are kept as comments
+  fn to_smtlib2(&self) -> String;
 }
 
 impl ConstraintsTrait for Constraints {
-  fn sat(&self) -> bool {
-    use z3::*;
-    let z3_ctx = Context::new(&z3::Config::default());
-    let solver = Solver::new(&z3_ctx);
+  fn to_smtlib2(&self) -> String {
     let mut symbol_map = HashMap::new();
-    let mut symbol_id = 0;
-    for Constraint { cond, branch } in self.iter() {
-      match cond.into_z3_ast(&mut symbol_map, &mut symbol_id, &z3_ctx) {
-        Some(cond) => {
-          let formula = if *branch { cond } else { cond.not() };
-          solver.assert(&formula);
+    let assertions = self
+      .iter()
+      .map(|constraint| {
+        let header = format!("; {} at node {}", constraint.loc, constraint.node_id);
+        match constraint.cond.to_smtlib(&mut symbol_map) {
+          Some(cond) if constraint.branch => format!("{}\n(assert {})", header, cond),
+          Some(cond) => format!("{}\n(assert (not {}))", header, cond),
+          None => format!("{} cannot be encoded", header),
         }
-        _ => (),
-      }
-    }
-    match solver.check() {
-      SatResult::Sat | SatResult::Unknown => true,
-      _ => false,
-    }
+      })
+      .collect::<Vec<_>>();
+    let mut symbol_ids = symbol_map.values().collect::<Vec<_>>();
+    symbol_ids.sort();
+    let declarations = symbol_ids.into_iter().map(|id| format!("(declare-const s{} Int)", id));
+    declarations
+      .chain(assertions)
+      .chain(std::iter::once("(check-sat)".to_string()))
+      .collect::<Vec<_>>()
+      .join("\n")
+      + "\n"
   }
 }
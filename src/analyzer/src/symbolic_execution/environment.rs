@@ -12,10 +12,11 @@ pub struct Environment<'ctx> {
   pub call_id: usize,
   pub max_work: usize,
   pub rng: StdRng,
+  pub solver: Solver,
 }
 
 impl<'ctx> Environment<'ctx> {
-  pub fn new(slice: &Slice<'ctx>, max_work: usize, seed: u64) -> Self {
+  pub fn new(slice: &Slice<'ctx>, max_work: usize, seed: u64, solver: Solver) -> Self {
     Self {
       slice: slice.clone(),
       work_list: vec![],
@@ -23,6 +24,7 @@ impl<'ctx> Environment<'ctx> {
       call_id: 0,
       max_work: max_work,
       rng: StdRng::seed_from_u64(seed),
+      solver,
     }
   }
 
@@ -1,13 +1,17 @@
 use indicatif::*;
 use llir::{values::*, Module};
+use petgraph::Direction;
 use rayon::prelude::*;
 use std::collections::HashMap;
 use std::fs;
 use std::rc::Rc;
+use std::time::Duration;
 
 use crate::call_graph::*;
+use crate::incremental::*;
 use crate::semantics::{rced::*, *};
 use crate::slicer::*;
+use crate::target_spec::*;
 use crate::utils::*;
 
 use super::*;
@@ -19,6 +23,7 @@ where
   pub module: &'a Module<'ctx>,
   pub call_graph: &'a CallGraph<'ctx>,
   pub options: &'a O,
+  pub reused_slices: Option<&'a ReusedSlices>,
 }
 
 impl<'a, 'ctx, O> SymbolicExecutionContext<'a, 'ctx, O>
@@ -30,6 +35,15 @@ where
       module,
       call_graph,
       options,
+      reused_slices: None,
+    }
+  }
+
+  /// Skip the execution of the slices whose traces are reused from a previous run
+  pub fn with_reused_slices(self, reused_slices: &'a ReusedSlices) -> Self {
+    Self {
+      reused_slices: Some(reused_slices),
+      ..self
     }
   }
 
@@ -44,13 +58,20 @@ where
   ) -> Option<Instruction<'ctx>> {
     match func.first_block() {
       Some(block) => {
+        // Arguments beyond the declared ones are bound to the variadic part of the frame
+        let mut arguments = args;
+        let var_args = arguments.split_off(func.num_arguments().min(arguments.len()));
         let stack_frame = StackFrame {
           function: func,
           instr: Some((instr_node_id, instr)),
           memory: LocalMemory::new(),
-          arguments: args,
+          arguments,
+          var_args,
         };
         state.stack.push(stack_frame);
+        state.frames.push_back(func);
+        let recursion_depth = state.stack.num_frames_of(func) - 1;
+        state.recursion_depth = state.recursion_depth.max(recursion_depth);
         self.execute_block(block, state, env)
       }
       None => panic!("The executed function is empty"),
@@ -99,7 +120,12 @@ where
             Unreachable(unr) => self.transfer_unreachable_instr(unr, state, env),
             Binary(bin) => self.transfer_binary_instr(bin, state, env),
             Unary(una) => self.transfer_unary_instr(una, state, env),
-            _ => self.transfer_instr(instr, state, env),
+            ExtractValue(ev) => self.transfer_extract_value_instr(ev, state, env),
+            Other(_) if instr.is_va_arg() => self.transfer_va_arg_instr(instr, state, env),
+            _ => match instr.as_call_like() {
+              Some(invoke) => self.transfer_call_instr(invoke, state, env),
+              None => self.transfer_instr(instr, state, env),
+            },
           }
         }
         None => None,
@@ -190,11 +216,11 @@ where
     &self,
     instr: ReturnInstruction<'ctx>,
     state: &mut State<'ctx>,
-    _: &mut Environment<'ctx>,
+    env: &mut Environment<'ctx>,
   ) -> Option<Instruction<'ctx>> {
     // First evaluate the return operand. There might not be one
     let val = instr.op().map(|val| self.eval_operand_value(state, val));
-    state.trace.push(TraceNode {
+    state.push_node(TraceNode {
       instr: instr.as_instruction(),
       semantics: Semantics::Ret { op: val.clone() },
       result: None,
@@ -202,16 +228,18 @@ where
 
     // Then we peek the stack frame
     let stack_frame = state.stack.pop().unwrap(); // There has to be a stack on the top
+    state.frames.pop_back();
+    state.block_trace_iter.visit_return(stack_frame.function);
     match stack_frame.instr {
       Some((node_id, call_site)) => {
         let call_site_frame = state.stack.top_mut(); // If call site exists then there must be a stack top
         if let Some(op0) = val {
           if stack_frame.function.get_function_type().has_return_type() {
             state.trace[node_id].result = Some(op0.clone());
-            call_site_frame.memory.insert(call_site.as_instruction(), op0);
+            call_site_frame.memory.insert(call_site.result_instruction(), op0);
           }
         }
-        call_site.next_instruction()
+        self.continue_after_call(call_site, state, env)
       }
 
       // If no call site then we are in the entry function. We will end the execution
@@ -230,7 +258,7 @@ where
   ) -> Option<Instruction<'ctx>> {
     let curr_blk = instr.parent_block(); // We assume instruction always has parent block
     state.prev_block = Some(curr_blk);
-    state.trace.push(TraceNode {
+    state.push_node(TraceNode {
       instr: instr.as_instruction(),
       semantics: Semantics::UncondBr {
         end_loop: instr.is_loop_jump().unwrap_or(false),
@@ -261,15 +289,21 @@ where
           from: curr_blk,
           to: block,
         };
-        let visited = state.visited_branch.contains(&br_dir);
-        if !visited {
+        if !self.is_branch_feasible(state, &comparison, br.is_then(), is_loop_blk, env) {
+          // If the guided branch cannot happen, stop the execution with PathUnsat
+          state.finish_state = FinishState::PathUnsat;
+          None
+        } else if state
+          .visited_branch
+          .can_visit(&br_dir, self.options.loop_unroll_bound())
+        {
           if let Some(comparison) = comparison {
-            if !is_loop_blk {
-              state.add_constraint(comparison, br.is_then());
+            if self.records_branch_constraint(state, &comparison, is_loop_blk) {
+              state.add_constraint(instr.as_instruction(), comparison, br.is_then());
             }
           }
-          state.visited_branch.insert(br_dir);
-          state.trace.push(TraceNode {
+          state.visited_branch.visit(br_dir);
+          state.push_node(TraceNode {
             instr: instr.as_instruction(),
             result: None,
             semantics: Semantics::CondBr {
@@ -294,24 +328,27 @@ where
           from: curr_blk,
           to: instr.else_block(),
         };
-        let visited_then = state.visited_branch.contains(&then_br);
-        let visited_else = state.visited_branch.contains(&else_br);
-        if !visited_then {
+        let unroll_bound = self.options.loop_unroll_bound();
+        let then_unvisited = state.visited_branch.can_visit(&then_br, unroll_bound);
+        let else_unvisited = state.visited_branch.can_visit(&else_br, unroll_bound);
+        let can_visit_then = then_unvisited && self.is_branch_feasible(state, &comparison, true, is_loop_blk, env);
+        let can_visit_else = else_unvisited && self.is_branch_feasible(state, &comparison, false, is_loop_blk, env);
+        if can_visit_then {
           // Check if we need to add a work for else branch
-          if !visited_else && env.can_add_work() {
+          if can_visit_else && env.can_add_work() {
             // First add else branch into work
             let mut else_state = state.clone();
 
             // Add constraint
             if let Some(comparison) = comparison.clone() {
-              if !is_loop_blk {
-                else_state.add_constraint(comparison, false);
+              if self.records_branch_constraint(state, &comparison, is_loop_blk) {
+                else_state.add_constraint(instr.as_instruction(), comparison, false);
               }
             }
 
             // Update state
-            else_state.visited_branch.insert(else_br);
-            else_state.trace.push(TraceNode {
+            else_state.visited_branch.visit(else_br);
+            else_state.push_node(TraceNode {
               instr: instr.as_instruction(),
               result: None,
               semantics: Semantics::CondBr {
@@ -328,12 +365,12 @@ where
 
           // Then execute the then branch
           if let Some(comparison) = comparison {
-            if !is_loop_blk {
-              state.add_constraint(comparison, true);
+            if self.records_branch_constraint(state, &comparison, is_loop_blk) {
+              state.add_constraint(instr.as_instruction(), comparison, true);
             }
           }
-          state.visited_branch.insert(then_br);
-          state.trace.push(TraceNode {
+          state.visited_branch.visit(then_br);
+          state.push_node(TraceNode {
             instr: instr.as_instruction(),
             result: None,
             semantics: Semantics::CondBr {
@@ -343,15 +380,15 @@ where
             },
           });
           self.execute_block(instr.then_block(), state, env)
-        } else if !visited_else {
+        } else if can_visit_else {
           // Execute the else branch
           if let Some(comparison) = comparison {
-            if !is_loop_blk {
-              state.add_constraint(comparison.clone(), false);
+            if self.records_branch_constraint(state, &comparison, is_loop_blk) {
+              state.add_constraint(instr.as_instruction(), comparison.clone(), false);
             }
           }
-          state.visited_branch.insert(else_br);
-          state.trace.push(TraceNode {
+          state.visited_branch.visit(else_br);
+          state.push_node(TraceNode {
             instr: instr.as_instruction(),
             semantics: Semantics::CondBr {
               cond,
@@ -361,6 +398,10 @@ where
             result: None,
           });
           self.execute_block(instr.else_block(), state, env)
+        } else if then_unvisited || else_unvisited {
+          // If the unvisited branches are all infeasible, stop the execution with PathUnsat
+          state.finish_state = FinishState::PathUnsat;
+          None
         } else {
           // If both then and else are visited, stop the execution with BranchExplored
           state.finish_state = FinishState::BranchExplored;
@@ -370,6 +411,35 @@ where
     }
   }
 
+  /// Whether taking the branch keeps the path constraints satisfiable. The
+  /// check is skipped when pruning is disabled or no constraint is recorded
+  fn is_branch_feasible(
+    &self,
+    state: &State<'ctx>,
+    comparison: &Option<Comparison>,
+    branch: bool,
+    is_loop_blk: bool,
+    env: &mut Environment<'ctx>,
+  ) -> bool {
+    match comparison {
+      Some(comparison)
+        if !self.options.no_prune_infeasible_branch()
+          && self.records_branch_constraint(state, comparison, is_loop_blk) =>
+      {
+        env.solver.sat_with(&state.constraints, comparison, branch)
+      }
+      _ => true,
+    }
+  }
+
+  /// Constraints of the loop entry branches are only recorded when loops are
+  /// unrolled, since otherwise only the first iteration is ever explored. A
+  /// loop entry comparison whose value did not change since a previous
+  /// iteration is not recorded again, as leaving the loop would contradict it
+  fn records_branch_constraint(&self, state: &State<'ctx>, comparison: &Comparison, is_loop_blk: bool) -> bool {
+    !is_loop_blk || (self.options.loop_unroll_bound() > 0 && !state.constraints.iter().any(|c| &c.cond == comparison))
+  }
+
   pub fn transfer_br_instr(
     &self,
     instr: BranchInstruction<'ctx>,
@@ -408,21 +478,24 @@ where
       semantics: Semantics::Switch { cond },
       result: None,
     };
-    state.trace.push(node);
+    state.push_node(node);
 
     // Insert branches as work if not visited
     for bd in branches {
-      if !state.visited_branch.contains(&bd) && env.can_add_work() {
+      if state.visited_branch.can_visit(&bd, self.options.loop_unroll_bound()) && env.can_add_work() {
         let mut br_state = state.clone();
-        br_state.visited_branch.insert(bd);
+        br_state.visited_branch.visit(bd);
         let br_work = Work::new(bd.to, br_state);
         env.add_work(br_work);
       }
     }
 
     // Execute default branch
-    if !state.visited_branch.contains(&default_br) {
-      state.visited_branch.insert(default_br);
+    if state
+      .visited_branch
+      .can_visit(&default_br, self.options.loop_unroll_bound())
+    {
+      state.visited_branch.visit(default_br);
       self.execute_block(instr.default_destination(), state, env)
     } else {
       state.finish_state = FinishState::BranchExplored;
@@ -430,14 +503,52 @@ where
     }
   }
 
+  /// Continue after a call returns. An `invoke` is a terminator, so the
+  /// execution jumps to its normal destination
+  fn continue_after_call(
+    &self,
+    instr: CallInstruction<'ctx>,
+    state: &mut State<'ctx>,
+    env: &mut Environment<'ctx>,
+  ) -> Option<Instruction<'ctx>> {
+    match instr.normal_destination() {
+      Some(block) => {
+        state.prev_block = Some(instr.parent_block());
+        self.execute_block(block, state, env)
+      }
+      None => instr.next_instruction(),
+    }
+  }
+
   pub fn transfer_call_instr(
     &self,
     instr: CallInstruction<'ctx>,
     state: &mut State<'ctx>,
     env: &mut Environment<'ctx>,
   ) -> Option<Instruction<'ctx>> {
+    // Record the source variables described by debug intrinsics
+    if let Some((kind, operand, var)) = instr.debug_variable() {
+      match (kind, operand) {
+        (DebugIntrinsicKind::Address, Operand::Instruction(alloca)) => {
+          // Arguments are stored to their alloca before it is declared
+          if let Value::AllocOf(value) = &*self.eval_operand_value(state, operand) {
+            state.var_names.name_value(value.clone(), var.clone().into());
+          }
+          state.var_names.declare(alloca, var);
+        }
+        (DebugIntrinsicKind::Value, _) => {
+          let value = self.eval_operand_value(state, operand);
+          state.var_names.name_value(value, var.into());
+        }
+        _ => {}
+      }
+    }
+
     // If is intrinsic call, skip the instruction
-    if instr.is_dummy_intrinsic_call() {
+    if instr.is_dummy_intrinsic_call()
+      || self.transfer_value_intrinsic(instr, state)
+      || self.transfer_var_arg_intrinsic(instr, state)
+    {
       instr.next_instruction()
     } else {
       // Visit call for block trace guidance
@@ -445,19 +556,34 @@ where
 
       // Check if stepping in the function, and get the function Value and also
       // maybe function reference
+      let can_step_in = |func: Function<'ctx>, state: &State<'ctx>| {
+        state.stack.num_frames_of(func) <= self.options.max_recursion_depth()
+          && func != env.slice.callee
+          && !func.is_declaration_only()
+          && env.slice.functions.contains(&func)
+      };
       let (step_in, func_value, func) = match instr.callee_function() {
-        Some(func) => {
-          let step_in = !state.stack.has_function(func)
-            && func != env.slice.callee
-            && !func.is_declaration_only()
-            && env.slice.functions.contains(&func);
-          (step_in, Rc::new(Value::Func(func.simp_name())), Some(func))
-        }
+        Some(func) => (
+          can_step_in(func, state),
+          Rc::new(Value::Func(func.simp_name())),
+          Some(func),
+        ),
         None => {
           if instr.is_inline_asm_call() {
             (false, Rc::new(Value::Asm), None)
           } else {
-            (false, Rc::new(Value::FuncPtr), None)
+            // Function pointers loaded from constant globals are resolved
+            match self.constant_callee(state, instr.callee()) {
+              Some(func) => (
+                can_step_in(func, state),
+                Rc::new(Value::Func(func.simp_name())),
+                Some(func),
+              ),
+              None => match &*self.eval_operand_value(state, instr.callee()) {
+                Value::Func(name) => (false, Rc::new(Value::Func(name.clone())), None),
+                _ => (false, Rc::new(Value::FuncPtr), None),
+              },
+            }
           }
         }
       };
@@ -468,6 +594,7 @@ where
         .into_iter()
         .map(|v| self.eval_operand_value(state, v))
         .collect::<Vec<_>>();
+      self.transfer_memory_intrinsic(instr, &args, state);
 
       // Cache the node id for this call
       let node_id = state.trace.len();
@@ -482,11 +609,26 @@ where
         semantics,
         result: None,
       };
-      state.trace.push(node);
+      state.push_node(node);
 
-      // Update the target_node in state if the target is now visited
-      if instr == env.slice.instr && state.target_node.is_none() {
-        state.target_node = Some(node_id);
+      // Record the target node in state every time the target is visited
+      if instr == env.slice.instr {
+        state.target_nodes.push(node_id);
+      }
+
+      // The trace ends at a call that never returns
+      if instr.is_noreturn_call() {
+        state.finish_state = FinishState::Exited;
+        return None;
+      }
+
+      // An invoke can also unwind to its landing pad, which is explored as another work
+      if let Some(unwind_block) = instr.unwind_destination() {
+        if env.can_add_work() {
+          let mut unwind_state = state.clone();
+          unwind_state.prev_block = Some(instr.parent_block());
+          env.add_work(Work::new(unwind_block, unwind_state));
+        }
       }
 
       // Check if we need to get into the function
@@ -508,12 +650,150 @@ where
           state.trace[node_id].result = Some(result.clone());
 
           // Insert a result to the stack frame memory
-          state.stack.top_mut().memory.insert(instr.as_instruction(), result);
+          state.stack.top_mut().memory.insert(instr.result_instruction(), result);
         }
 
         // Execute the next instruction directly
-        instr.next_instruction()
+        self.continue_after_call(instr, state, env)
+      }
+    }
+  }
+
+  /// Model the intrinsics passing or computing a value, e.g. `llvm.expect`
+  /// wrapping the conditions of `likely()` & `unlikely()`, so that the value
+  /// flows to the comparisons instead of an opaque call result. Returns
+  /// whether the call is such an intrinsic
+  fn transfer_value_intrinsic(&self, instr: CallInstruction<'ctx>, state: &mut State<'ctx>) -> bool {
+    let name = match instr.intrinsic_name() {
+      Some(name) => name,
+      None => return false,
+    };
+    let args = instr.arguments();
+    let res = match name.split('.').nth(1) {
+      Some("expect") => self.eval_operand_value(state, args[0]),
+      Some("objectsize") => Rc::new(Value::Sym(state.new_symbol_id())),
+      Some(op @ "bswap") | Some(op @ "ctpop") => {
+        let folded = match (&*self.eval_operand_value(state, args[0]), instr.get_type()) {
+          (Value::Int(i), llir::types::Type::Int(ty)) => fold_bit_intrinsic(op, *i, ty.width()),
+          _ => None,
+        };
+        match folded {
+          Some(i) => Rc::new(Value::Int(i)),
+          None => return false,
+        }
+      }
+      Some(op) if name.contains(".with.overflow.") => {
+        // The result is kept as the arithmetic, the overflow bit is extracted as a new symbol
+        let op = match op {
+          "sadd" | "uadd" => BinOp::Add,
+          "ssub" | "usub" => BinOp::Sub,
+          "smul" | "umul" => BinOp::Mul,
+          _ => return false,
+        };
+        let op0 = self.eval_operand_value(state, args[0]);
+        let op1 = self.eval_operand_value(state, args[1]);
+        let res = Rc::new(Value::Bin {
+          op,
+          op0: op0.clone(),
+          op1: op1.clone(),
+        });
+        state.push_node(TraceNode {
+          instr: instr.as_instruction(),
+          semantics: Semantics::Bin { op, op0, op1 },
+          result: Some(res.clone()),
+        });
+        res
+      }
+      _ => return false,
+    };
+    state.stack.top_mut().memory.insert(instr.as_instruction(), res);
+    true
+  }
+
+  /// Model `llvm.va_start`, which points the `va_list` to the variadic
+  /// arguments of the frame, and `llvm.va_copy`, which copies its position.
+  /// `llvm.va_end` is skipped. Returns whether the call is one of them
+  fn transfer_var_arg_intrinsic(&self, instr: CallInstruction<'ctx>, state: &mut State<'ctx>) -> bool {
+    let args = instr.arguments();
+    match instr.intrinsic_name().as_ref().and_then(|name| name.split('.').nth(1)) {
+      Some("va_start") => {
+        let list = self.eval_operand_value(state, args[0]);
+        let area = self.var_arg_area(state);
+
+        // The registers of the x86_64 `va_list` are used up, so that every
+        // argument is read from the overflow area
+        let positions = vec![area.clone(), Rc::new(Value::Int(48)), Rc::new(Value::Int(176)), area];
+        for (loc, position) in var_list_locations(&list).into_iter().zip(positions) {
+          state.memory.insert(loc, position);
+        }
+        true
       }
+      Some("va_copy") => {
+        let dst = self.eval_operand_value(state, args[0]);
+        let src = self.eval_operand_value(state, args[1]);
+        for (dst_loc, src_loc) in var_list_locations(&dst).into_iter().zip(var_list_locations(&src)) {
+          if let Some(position) = state.memory.get(&src_loc).cloned() {
+            state.memory.insert(dst_loc, position);
+          }
+        }
+        true
+      }
+      Some("va_end") => true,
+      _ => false,
+    }
+  }
+
+  /// Lay out the variadic arguments of the frame in a new area, one slot of 8
+  /// bytes per argument
+  fn var_arg_area(&self, state: &mut State<'ctx>) -> Rc<Value> {
+    let area = Rc::new(Value::Sym(state.new_symbol_id()));
+    let mut slot = area.clone();
+    for arg in state.stack.top().var_args.clone() {
+      state.memory.insert(slot.clone(), arg);
+      slot = next_var_arg_slot(&slot);
+    }
+    area
+  }
+
+  /// Copy or set the memory written by `llvm.memcpy`, `llvm.memmove` and
+  /// `llvm.memset`, including the fields under the destination
+  fn transfer_memory_intrinsic(&self, instr: CallInstruction<'ctx>, args: &[Rc<Value>], state: &mut State<'ctx>) {
+    let name = match instr.intrinsic_name() {
+      Some(name) => name,
+      None => return,
+    };
+    match name.split('.').nth(1) {
+      Some("memcpy") | Some("memmove") => {
+        let (dst, src) = (&args[0], &args[1]);
+        let copied = state
+          .memory
+          .iter()
+          .filter(|(loc, _)| loc != &src)
+          .filter_map(|(loc, val)| Some((rebase_location(loc, src, dst)?, val.clone())))
+          .collect::<Vec<_>>();
+        let val = self.load_from_memory(state, src.clone());
+        state.memory.retain(|loc, _| rebase_location(loc, dst, dst).is_none());
+        state.memory.insert(dst.clone(), val);
+        state.memory.extend(copied);
+      }
+      Some("memset") => {
+        let dst = &args[0];
+        let val = match &*args[1] {
+          Value::Int(0) => Rc::new(Value::Int(0)),
+          _ => Rc::new(Value::Sym(state.new_symbol_id())),
+        };
+        let locs = state
+          .memory
+          .keys()
+          .filter(|loc| rebase_location(loc, dst, dst).is_some())
+          .cloned()
+          .collect::<Vec<_>>();
+        for loc in locs {
+          state.memory.insert(loc, val.clone());
+        }
+        state.memory.insert(dst.clone(), val);
+      }
+      _ => {}
     }
   }
 
@@ -535,32 +815,37 @@ where
   ) -> Option<Instruction<'ctx>> {
     let loc = self.eval_operand_value(state, instr.location());
     let val = self.eval_operand_value(state, instr.value());
+    self.store_to_memory(state, instr.location(), loc.clone(), val.clone());
+    state.var_names.access(instr.location(), &loc, &val);
+    let node = TraceNode {
+      instr: instr.as_instruction(),
+      semantics: Semantics::Store { loc, val },
+      result: None,
+    };
+    state.push_node(node);
+    instr.next_instruction()
+  }
 
+  /// Store the value at the location, then update the `AllocOf` of the
+  /// location operand
+  fn store_to_memory(&self, state: &mut State<'ctx>, location: Operand<'ctx>, loc: Rc<Value>, val: Rc<Value>) {
     // First insert into memory
     state.memory.insert(loc.clone(), val.clone());
 
     // Then update the AllocOf
     match (*loc).clone() {
-      Value::AllocOf(_) => match instr.location() {
+      Value::AllocOf(_) => match location {
         Operand::Instruction(loc_instr) => {
           state
             .stack
             .top_mut()
             .memory
-            .insert(loc_instr, Rc::new(Value::AllocOf(val.clone())));
+            .insert(loc_instr, Rc::new(Value::AllocOf(val)));
         }
         _ => {}
       },
       _ => {}
     };
-
-    let node = TraceNode {
-      instr: instr.as_instruction(),
-      semantics: Semantics::Store { loc, val },
-      result: None,
-    };
-    state.trace.push(node);
-    instr.next_instruction()
   }
 
   pub fn transfer_load_instr(
@@ -570,17 +855,76 @@ where
     _: &mut Environment<'ctx>,
   ) -> Option<Instruction<'ctx>> {
     let loc = self.eval_operand_value(state, instr.location());
-    let res = self.load_from_memory(state, loc.clone());
+    let res = match self.load_constant_global(state, instr.location()) {
+      Some(res) => res,
+      None => self.load_from_memory(state, loc.clone()),
+    };
+    state.var_names.access(instr.location(), &loc, &res);
     let node = TraceNode {
       instr: instr.as_instruction(),
       semantics: Semantics::Load { loc },
       result: Some(res.clone()),
     };
-    state.trace.push(node);
+    state.push_node(node);
     state.stack.top_mut().memory.insert(instr.as_instruction(), res);
     instr.next_instruction()
   }
 
+  /// Load the constant data at the location if it points into a constant
+  /// global, e.g. a flag, an entry of a constant table or a function pointer
+  /// inside of an ops struct
+  fn load_constant_global(&self, state: &mut State<'ctx>, location: Operand<'ctx>) -> Option<Rc<Value>> {
+    let data = self.constant_global_data(state, location)?;
+    Some(self.eval_constant_value(state, data))
+  }
+
+  /// The function called through a pointer loaded from a constant global
+  fn constant_callee(&self, state: &mut State<'ctx>, callee: Operand<'ctx>) -> Option<Function<'ctx>> {
+    match callee {
+      Operand::Instruction(Instruction::Load(ld)) => match self.constant_global_data(state, ld.location())? {
+        Constant::Function(func) => Some(func),
+        _ => None,
+      },
+      _ => None,
+    }
+  }
+
+  fn constant_global_data(&self, state: &mut State<'ctx>, location: Operand<'ctx>) -> Option<Constant<'ctx>> {
+    let (global, indices) = match location {
+      Operand::Constant(Constant::Global(glob)) => (glob, vec![]),
+      Operand::Constant(Constant::ConstExpr(ConstExpr::GetElementPtr(gep))) => match gep.location() {
+        Constant::Global(glob) => {
+          let indices = gep
+            .indices()
+            .into_iter()
+            .map(|index| match index {
+              Constant::Int(i) => Some(i.sext_value()),
+              _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+          (glob, indices)
+        }
+        _ => return None,
+      },
+      Operand::Instruction(Instruction::GetElementPtr(gep)) => match gep.location() {
+        Operand::Constant(Constant::Global(glob)) => {
+          let indices = gep
+            .indices()
+            .into_iter()
+            .map(|index| match &*self.eval_operand_value(state, index) {
+              Value::Int(i) => Some(*i),
+              _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+          (glob, indices)
+        }
+        _ => return None,
+      },
+      _ => return None,
+    };
+    global.constant_data(&indices)
+  }
+
   pub fn transfer_icmp_instr(
     &self,
     instr: ICmpInstruction<'ctx>,
@@ -601,7 +945,7 @@ where
       semantics,
       result: Some(res.clone()),
     };
-    state.trace.push(node);
+    state.push_node(node);
     state.stack.top_mut().memory.insert(instr.as_instruction(), res);
     instr.next_instruction()
   }
@@ -640,6 +984,7 @@ where
       loc: loc.clone(),
       indices: indices.clone(),
     });
+    state.var_names.gep(instr, &loc, &indices, &res);
     let node = TraceNode {
       instr: instr.as_instruction(),
       semantics: Semantics::GEP {
@@ -648,7 +993,7 @@ where
       },
       result: Some(res.clone()),
     };
-    state.trace.push(node);
+    state.push_node(node);
     state.stack.top_mut().memory.insert(instr.as_instruction(), res);
     instr.next_instruction()
   }
@@ -672,7 +1017,7 @@ where
       semantics: Semantics::Bin { op, op0: v0, op1: v1 },
       result: Some(res.clone()),
     };
-    state.trace.push(node);
+    state.push_node(node);
     state.stack.top_mut().memory.insert(instr.as_instruction(), res);
     instr.next_instruction()
   }
@@ -690,21 +1035,72 @@ where
       semantics: Semantics::Una { op, op0: op0.clone() },
       result: Some(op0.clone()),
     };
-    state.trace.push(node);
+    state.push_node(node);
     state.stack.top_mut().memory.insert(instr.as_instruction(), op0);
     instr.next_instruction()
   }
 
   pub fn transfer_unreachable_instr(
     &self,
-    _: UnreachableInstruction<'ctx>,
+    instr: UnreachableInstruction<'ctx>,
     state: &mut State<'ctx>,
     _: &mut Environment<'ctx>,
   ) -> Option<Instruction<'ctx>> {
-    state.finish_state = FinishState::Unreachable;
+    // An unreachable right after a call, e.g. the inline asm of `BUG()`, means
+    // that the call does not return
+    let follows_call = state.trace.last().map_or(false, |node| {
+      node.instr.parent_block() == instr.parent_block() && node.instr.as_call_like().is_some()
+    });
+    state.finish_state = if follows_call {
+      FinishState::Exited
+    } else {
+      FinishState::Unreachable
+    };
     None
   }
 
+  pub fn transfer_extract_value_instr(
+    &self,
+    instr: ExtractValueInstruction<'ctx>,
+    state: &mut State<'ctx>,
+    _: &mut Environment<'ctx>,
+  ) -> Option<Instruction<'ctx>> {
+    // Only the results of `*.with.overflow` intrinsics are modeled
+    let is_with_overflow = match instr.aggregate_operand() {
+      Operand::Instruction(Instruction::Call(call)) => call
+        .intrinsic_name()
+        .map_or(false, |name| name.contains(".with.overflow.")),
+      _ => false,
+    };
+    if is_with_overflow {
+      let res = match instr.extract_indices().as_slice() {
+        [0] => self.eval_operand_value(state, instr.aggregate_operand()),
+        _ => Rc::new(Value::Sym(state.new_symbol_id())),
+      };
+      state.stack.top_mut().memory.insert(instr.as_instruction(), res);
+    }
+    instr.next_instruction()
+  }
+
+  /// `va_arg` reads the argument at the position of the `va_list` and moves
+  /// the list to the next one, or reads a new symbol past the passed
+  /// arguments, e.g. in a variadic entry function. On x86_64 the front end
+  /// lowers `va_arg` to accesses of the `va_list` fields instead
+  pub fn transfer_va_arg_instr(
+    &self,
+    instr: Instruction<'ctx>,
+    state: &mut State<'ctx>,
+    _: &mut Environment<'ctx>,
+  ) -> Option<Instruction<'ctx>> {
+    let list = instr.va_arg_list().expect("A `va_arg` reads from a `va_list`");
+    let loc = self.eval_operand_value(state, list);
+    let slot = self.load_from_memory(state, loc.clone());
+    let value = self.load_from_memory(state, slot.clone());
+    self.store_to_memory(state, list, loc, next_var_arg_slot(&slot));
+    state.stack.top_mut().memory.insert(instr, value);
+    instr.next_instruction()
+  }
+
   pub fn transfer_instr(
     &self,
     instr: Instruction<'ctx>,
@@ -726,50 +1122,12 @@ where
     metadata: &mut MetaData,
     env: &mut Environment<'ctx>,
   ) {
-    match state.target_node {
-      Some(target_id) => match state.finish_state {
-        FinishState::ProperlyReturned => {
-          // Generate the trace for output
-          let raw_trace = TraceWithTarget::new(state.trace, target_id, state.statically_checked);
-          let trace = if !self.options.no_trace_reduction() {
-            raw_trace.reduce()
-          } else {
-            raw_trace
-          };
-
-          // Check block trace duplication
-          let block_trace = trace.block_trace();
-          if !env.has_duplicate(&block_trace) {
-            // Add block trace into environment
-            env.add_block_trace(block_trace);
-
-            // Check path satisfaction
-            if state.constraints.sat() {
-              // Need store
-              let trace_id = metadata.proper_trace_count;
-              let path = self.options.trace_target_slice_file_path(
-                env.slice.target_function_name().as_str(),
-                slice_id,
-                trace_id,
-              );
-
-              // If printing trace
-              if self.options.print_trace() && self.options.use_serial() {
-                println!("\nSlice {} Trace {} Log", slice_id, trace_id);
-                trace.print();
-              }
-
-              // Dump the json
-              dump_json(&trace.to_json(), path).expect("Cannot dump json");
-
-              // Increase the count in metadata
-              metadata.incr_proper();
-            } else {
-              metadata.incr_path_unsat()
-            }
-          } else {
-            metadata.incr_duplicated()
-          }
+    match state.target_node() {
+      Some(_) => match state.finish_state {
+        FinishState::ProperlyReturned | FinishState::Exited => self.dump_trace(state, slice_id, metadata, env),
+        // Traces cut before returning are only dumped when asked to be kept
+        FinishState::BranchExplored | FinishState::ExceedingMaxTraceLength if self.options.keep_truncated_traces() => {
+          self.dump_trace(state, slice_id, metadata, env)
         }
         FinishState::BranchExplored => {
           metadata.incr_branch_explored()
@@ -780,8 +1138,90 @@ where
         FinishState::Unreachable => {
           metadata.incr_unreachable()
         },
+        FinishState::PathUnsat => metadata.incr_path_unsat(),
+      },
+      None => match state.finish_state {
+        FinishState::PathUnsat => metadata.incr_path_unsat(),
+        _ => metadata.incr_no_target(),
       },
-      None => metadata.incr_no_target(),
+    }
+  }
+
+  fn dump_trace(&self, state: State<'ctx>, slice_id: usize, metadata: &mut MetaData, env: &mut Environment<'ctx>) {
+    // Generate the trace for output
+    let raw_trace = TraceWithTarget::new(
+      state.trace,
+      state.target_nodes,
+      state.statically_checked,
+      state.finish_state,
+      state.constraints,
+      state.var_names.names(),
+      state.recursion_depth,
+      state.node_frames,
+    );
+    let trace = if !self.options.no_trace_reduction() {
+      raw_trace.reduce()
+    } else {
+      raw_trace
+    };
+
+    // Check block trace duplication. Truncated traces are cut paths, so they
+    // neither count as nor are checked against the block traces of proper ones
+    let truncated = trace.truncation_reason().is_some();
+    let is_duplicate = if truncated {
+      false
+    } else {
+      let block_trace = trace.block_trace();
+      let is_duplicate = env.has_duplicate(&block_trace);
+      if !is_duplicate {
+        env.add_block_trace(block_trace);
+      }
+      is_duplicate
+    };
+    if !is_duplicate {
+      // Check path satisfaction
+      if env.solver.sat(&trace.constraints) {
+        // Need store. Truncated traces are numbered after the at most
+        // `max_trace_per_slice` proper ones
+        let trace_id = if truncated {
+          self.options.max_trace_per_slice() + metadata.truncated_trace_count
+        } else {
+          metadata.proper_trace_count
+        };
+        let path = self.options.trace_target_slice_file_path(
+          env.slice.target_function_name().as_str(),
+          slice_id,
+          trace_id,
+        );
+
+        // If printing trace
+        if self.options.print_trace() && self.options.use_serial() {
+          println!("\nSlice {} Trace {} Log", slice_id, trace_id);
+          trace.print();
+        }
+
+        // Dump the path constraints as a SMT-LIB2 query
+        if self.options.dump_smt() {
+          fs::write(path.with_extension("smt2"), trace.constraints.to_smtlib2()).expect("Cannot dump smt");
+        }
+
+        // Dump the json
+        dump_json(&trace.to_json(), path).expect("Cannot dump json");
+
+        // Increase the count in metadata
+        if truncated {
+          metadata.incr_truncated();
+        } else {
+          metadata.incr_proper();
+          if trace.exited() {
+            metadata.incr_exited();
+          }
+        }
+      } else {
+        metadata.incr_path_unsat()
+      }
+    } else {
+      metadata.incr_duplicated()
     }
   }
 
@@ -794,7 +1234,13 @@ where
 
   pub fn execute_slice(&self, slice: Slice<'ctx>, slice_id: usize) -> MetaData {
     let mut metadata = MetaData::new();
-    let mut env = Environment::new(&slice, self.options.max_work(), self.options.seed());
+    let solver = Solver::new(
+      self.options.solver(),
+      self.options.solver_command(),
+      Duration::from_millis(self.options.solver_timeout()),
+    )
+    .expect("The solver is validated before the execution");
+    let mut env = Environment::new(&slice, self.options.max_work(), self.options.seed(), solver);
 
     // Add a work to the environment list
     if self.options.no_prefilter_block_trace() {
@@ -803,10 +1249,15 @@ where
     } else {
       let block_traces = slice.block_traces(
         self.call_graph,
-        self.options.slice_depth() * 2,
+        slice.depth * 2,
         self.options.max_work() * 2,
+        self.options.loop_unroll_bound(),
+        self.options.post_target_guidance(),
       );
       for block_trace in block_traces {
+        if !env.can_add_work() {
+          break;
+        }
         if self.options.print_block_trace() {
           println!("{:?}", block_trace);
         }
@@ -833,6 +1284,26 @@ where
     fs::create_dir_all(path).map_err(|_| "Cannot create trace function slice folder".to_string())
   }
 
+  fn execute_target_slice(&self, target_name: &String, slice_id: usize, slice: Slice<'ctx>) -> MetaData {
+    let is_reused = match self.reused_slices {
+      Some(reused_slices) => reused_slices.is_reused(target_name, slice_id),
+      None => false,
+    };
+    let metadata_path = self.options.trace_target_slice_metadata_path(target_name, slice_id);
+    if is_reused {
+      // The counts of a reused slice are carried over from the previous run
+      load_json_t(&metadata_path).unwrap_or_else(|_| MetaData::new())
+    } else {
+      self
+        .initialize_traces_function_slice_folder(target_name, slice_id)
+        .unwrap();
+      let metadata = self.execute_slice(slice, slice_id);
+      let metadata_json = serde_json::to_value(&metadata).expect("Cannot turn metadata into json");
+      dump_json(&metadata_json, metadata_path).expect("Cannot dump metadata json");
+      metadata
+    }
+  }
+
   pub fn execute_target_slices(
     &self,
     target_name: &String,
@@ -844,10 +1315,7 @@ where
         MetaData::new(),
         |meta: MetaData, (id, slice): (usize, Slice<'ctx>)| {
           let slice_id = slice_id_offset + id;
-          self
-            .initialize_traces_function_slice_folder(target_name, slice_id)
-            .unwrap();
-          meta.combine(self.execute_slice(slice, slice_id))
+          meta.combine(self.execute_target_slice(target_name, slice_id, slice))
         },
       )
     } else {
@@ -859,10 +1327,7 @@ where
           || MetaData::new(),
           |meta: MetaData, (id, slice): (usize, Slice<'ctx>)| {
             let slice_id = slice_id_offset + id;
-            self
-              .initialize_traces_function_slice_folder(target_name, slice_id)
-              .unwrap();
-            meta.combine(self.execute_slice(slice, slice_id))
+            meta.combine(self.execute_target_slice(target_name, slice_id, slice))
           },
         )
         .progress_count(num_slices as u64)
@@ -870,12 +1335,48 @@ where
     }
   }
 
+  /// Execute the slices of a target using the options overridden by the target spec, if any
+  fn execute_target_slices_with_spec(
+    &self,
+    target_name: &String,
+    slice_id_offset: usize,
+    slices: Vec<Slice<'ctx>>,
+  ) -> MetaData {
+    let overrides = match self.options.target_spec() {
+      Some(spec) => {
+        // Resolve from all the callees of the target, as the slicer does, not only the sampled ones
+        let graph = &self.call_graph.graph;
+        spec.overrides_of_target(
+          graph
+            .node_indices()
+            .filter(|id| graph.neighbors_directed(*id, Direction::Incoming).next().is_some())
+            .map(|id| graph[id])
+            .filter(|callee| &callee.simp_name() == target_name),
+        )
+      }
+      None => None,
+    };
+    match overrides {
+      Some(overrides) => {
+        let target_options = TargetOptions::new(self.options, overrides);
+        let target_ctx = SymbolicExecutionContext {
+          module: self.module,
+          call_graph: self.call_graph,
+          options: &target_options,
+          reused_slices: self.reused_slices,
+        };
+        target_ctx.execute_target_slices(target_name, slice_id_offset, slices)
+      }
+      None => self.execute_target_slices(target_name, slice_id_offset, slices),
+    }
+  }
+
   pub fn execute_target_slices_map(&self, target_slices_map: HashMap<String, (usize, Vec<Slice<'ctx>>)>) -> MetaData {
     if self.options.use_serial() {
       target_slices_map
         .into_iter()
         .fold(MetaData::new(), |meta, (target_name, (offset, slices))| {
-          meta.combine(self.execute_target_slices(&target_name, offset, slices))
+          meta.combine(self.execute_target_slices_with_spec(&target_name, offset, slices))
         })
     } else {
       let num_targets = target_slices_map.len();
@@ -884,7 +1385,7 @@ where
         .fold(
           || MetaData::new(),
           |meta, (target_name, (offset, slices))| {
-            meta.combine(self.execute_target_slices(&target_name, offset, slices))
+            meta.combine(self.execute_target_slices_with_spec(&target_name, offset, slices))
           },
         )
         .progress_count(num_targets as u64)
@@ -892,3 +1393,56 @@ where
     }
   }
 }
+
+/// Locations holding the position of a `va_list`: the list itself, a pointer
+/// to the next argument read by `va_arg` instructions, then the `gp_offset`,
+/// `fp_offset` & `overflow_arg_area` fields of the x86_64 `__va_list_tag`
+fn var_list_locations(list: &Rc<Value>) -> Vec<Rc<Value>> {
+  let field = |index| {
+    Rc::new(Value::GEP {
+      loc: list.clone(),
+      indices: vec![Rc::new(Value::Int(0)), Rc::new(Value::Int(index))],
+    })
+  };
+  vec![list.clone(), field(0), field(1), field(2)]
+}
+
+/// The slot of the variadic argument after the one in the slot, as the
+/// front end moves the x86_64 overflow area
+fn next_var_arg_slot(slot: &Rc<Value>) -> Rc<Value> {
+  Rc::new(Value::GEP {
+    loc: slot.clone(),
+    indices: vec![Rc::new(Value::Int(8))],
+  })
+}
+
+/// The location with the base its GEP chain starts from replaced by the new
+/// base, or `None` when the location is not under the base
+fn rebase_location(loc: &Rc<Value>, base: &Rc<Value>, new_base: &Rc<Value>) -> Option<Rc<Value>> {
+  if loc == base {
+    return Some(new_base.clone());
+  }
+  match &**loc {
+    Value::GEP { loc, indices } => Some(Rc::new(Value::GEP {
+      loc: rebase_location(loc, base, new_base)?,
+      indices: indices.clone(),
+    })),
+    _ => None,
+  }
+}
+
+/// Fold a bit manipulation intrinsic on a constant of the given bit width
+fn fold_bit_intrinsic(op: &str, i: i64, width: u32) -> Option<i64> {
+  if width == 0 || width > 64 || width % 8 != 0 {
+    return None;
+  }
+  let shift = 64 - width;
+  let bits = (i as u64) << shift >> shift;
+  let res = match op {
+    "bswap" => bits.swap_bytes() >> shift,
+    "ctpop" => bits.count_ones() as u64,
+    _ => return None,
+  };
+  // Sign extend like the other integer constants
+  Some(((res << shift) as i64) >> shift)
+}
@@ -1,5 +1,5 @@
+use im_rc::HashMap;
 use llir::values::*;
-use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
 use crate::semantics::rced::*;
@@ -12,6 +12,9 @@ pub struct StackFrame<'ctx> {
   pub instr: Option<(usize, CallInstruction<'ctx>)>,
   pub memory: LocalMemory<'ctx>,
   pub arguments: Vec<Rc<Value>>,
+
+  /// Arguments passed to a variadic function beyond the declared ones
+  pub var_args: Vec<Rc<Value>>,
 }
 
 impl<'ctx> StackFrame<'ctx> {
@@ -23,6 +26,7 @@ impl<'ctx> StackFrame<'ctx> {
       arguments: (0..function.num_arguments())
         .map(|i| Rc::new(Value::Arg(i as usize)))
         .collect(),
+      var_args: Vec::new(),
     }
   }
 }
@@ -35,6 +39,9 @@ pub trait StackTrait<'ctx> {
   fn top_mut(&mut self) -> &mut StackFrame<'ctx>;
 
   fn has_function(&self, func: Function<'ctx>) -> bool;
+
+  /// Number of frames of the function on the stack
+  fn num_frames_of(&self, func: Function<'ctx>) -> usize;
 }
 
 impl<'ctx> StackTrait<'ctx> for Stack<'ctx> {
@@ -50,6 +57,10 @@ impl<'ctx> StackTrait<'ctx> for Stack<'ctx> {
   fn has_function(&self, func: Function<'ctx>) -> bool {
     self.iter().find(|frame| frame.function == func).is_some()
   }
+
+  fn num_frames_of(&self, func: Function<'ctx>) -> usize {
+    self.iter().filter(|frame| frame.function == func).count()
+  }
 }
 
 pub type Memory = HashMap<Rc<Value>, Rc<Value>>;
@@ -60,4 +71,23 @@ pub struct BranchDirection<'ctx> {
   pub to: Block<'ctx>,
 }
 
-pub type VisitedBranch<'ctx> = HashSet<BranchDirection<'ctx>>;
+/// Number of times each branch direction is taken
+pub type VisitedBranch<'ctx> = HashMap<BranchDirection<'ctx>, usize>;
+
+pub trait VisitedBranchTrait<'ctx> {
+  fn visit(&mut self, br: BranchDirection<'ctx>);
+
+  /// Whether the branch can be taken again when every loop can be unrolled
+  /// `unroll_bound` times, i.e. every branch can be taken `unroll_bound + 1` times
+  fn can_visit(&self, br: &BranchDirection<'ctx>, unroll_bound: usize) -> bool;
+}
+
+impl<'ctx> VisitedBranchTrait<'ctx> for VisitedBranch<'ctx> {
+  fn visit(&mut self, br: BranchDirection<'ctx>) {
+    *self.entry(br).or_insert(0) += 1;
+  }
+
+  fn can_visit(&self, br: &BranchDirection<'ctx>, unroll_bound: usize) -> bool {
+    self.get(br).map_or(0, |count| *count) <= unroll_bound
+  }
+}
@@ -1,4 +1,6 @@
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MetaData {
   pub proper_trace_count: usize,
   pub path_unsat_trace_count: usize,
@@ -7,6 +9,12 @@ pub struct MetaData {
   pub no_target_trace_count: usize,
   pub exceeding_length_trace_count: usize,
   pub unreachable_trace_count: usize,
+
+  /// Proper traces ending at a call that never returns, also counted as proper
+  pub exited_trace_count: usize,
+
+  /// Kept truncated traces, which are not counted as proper
+  pub truncated_trace_count: usize,
   pub explored_trace_count: usize,
 }
 
@@ -20,6 +28,8 @@ impl MetaData {
       no_target_trace_count: 0,
       exceeding_length_trace_count: 0,
       unreachable_trace_count: 0,
+      exited_trace_count: 0,
+      truncated_trace_count: 0,
       explored_trace_count: 0,
     }
   }
@@ -33,6 +43,8 @@ impl MetaData {
       no_target_trace_count: self.no_target_trace_count + other.no_target_trace_count,
       exceeding_length_trace_count: self.exceeding_length_trace_count + other.exceeding_length_trace_count,
       unreachable_trace_count: self.unreachable_trace_count + other.unreachable_trace_count,
+      exited_trace_count: self.exited_trace_count + other.exited_trace_count,
+      truncated_trace_count: self.truncated_trace_count + other.truncated_trace_count,
       explored_trace_count: self.explored_trace_count + other.explored_trace_count,
     }
   }
@@ -71,4 +83,13 @@ impl MetaData {
     self.unreachable_trace_count += 1;
     self.explored_trace_count += 1;
   }
+
+  pub fn incr_exited(&mut self) {
+    self.exited_trace_count += 1;
+  }
+
+  pub fn incr_truncated(&mut self) {
+    self.truncated_trace_count += 1;
+    self.explored_trace_count += 1;
+  }
 }
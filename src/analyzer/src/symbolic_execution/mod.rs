@@ -5,8 +5,10 @@ mod execution;
 mod memory;
 mod metadata;
 mod options;
+mod solver;
 mod state;
 mod trace;
+mod var_names;
 mod work;
 
 pub use block_tracer::*;
@@ -16,6 +18,8 @@ pub use execution::*;
 pub use memory::*;
 pub use metadata::*;
 pub use options::*;
+pub use solver::*;
 pub use state::*;
 pub use trace::*;
+pub use var_names::*;
 pub use work::*;
@@ -1,10 +1,20 @@
 use crate::options::*;
+use crate::target_spec::*;
+
+use super::solver::*;
 
 pub trait SymbolicExecutionOptions: GeneralOptions + IOOptions + Send + Sync {
   fn slice_depth(&self) -> usize;
 
   fn max_work(&self) -> usize;
 
+  /// Number of times each loop can be unrolled in block traces and execution
+  fn loop_unroll_bound(&self) -> usize;
+
+  /// Number of times a function can be stepped into again while it is on the
+  /// stack. Recursive calls beyond the bound are treated as opaque calls
+  fn max_recursion_depth(&self) -> usize;
+
   fn no_random_work(&self) -> bool;
 
   fn max_node_per_trace(&self) -> usize;
@@ -17,7 +27,33 @@ pub trait SymbolicExecutionOptions: GeneralOptions + IOOptions + Send + Sync {
 
   fn no_prefilter_block_trace(&self) -> bool;
 
+  /// Dump the traces reaching the target but cut by the trace length limit or
+  /// by an explored branch, marked as truncated and numbered after the proper
+  /// traces from `max_trace_per_slice` on
+  fn keep_truncated_traces(&self) -> bool;
+
+  /// Also guide the execution from the target call to the exits of the caller
+  /// and of the functions up the call stack
+  fn post_target_guidance(&self) -> bool;
+
+  /// Do not check the feasibility of branches when forking, only when the
+  /// execution finishes
+  fn no_prune_infeasible_branch(&self) -> bool;
+
+  fn solver(&self) -> SolverKind;
+
+  /// Command starting the external solver of the `smt-lib` solver
+  fn solver_command(&self) -> &str;
+
+  /// Milliseconds the external solver is given to answer a query
+  fn solver_timeout(&self) -> u64;
+
   fn print_block_trace(&self) -> bool;
 
   fn print_trace(&self) -> bool;
+
+  /// Dump the path constraints of each trace as a SMT-LIB2 file next to the trace
+  fn dump_smt(&self) -> bool;
+
+  fn target_spec(&self) -> &Option<TargetSpec>;
 }
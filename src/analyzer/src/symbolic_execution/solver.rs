@@ -0,0 +1,422 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::constraints::*;
+use crate::semantics::rced::*;
+
+/// The solver used to check path constraints
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SolverKind {
+  /// Z3 linked into the analyzer, only available with the `z3` feature
+  Z3,
+
+  /// An external solver speaking SMT-LIB2 over stdin & stdout
+  SmtLib,
+
+  /// Consider every path satisfiable, for fast dry runs
+  AlwaysSat,
+}
+
+impl FromStr for SolverKind {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "z3" => Ok(Self::Z3),
+      "smt-lib" => Ok(Self::SmtLib),
+      "always-sat" => Ok(Self::AlwaysSat),
+      _ => Err(format!("Unknown solver {}", s)),
+    }
+  }
+}
+
+/// A constraint with an id that stays the same across the queries of a slice,
+/// so that backends can reuse the encoding of the constraint
+pub struct InternedConstraint<'a> {
+  pub id: usize,
+  pub cond: &'a Comparison,
+  pub branch: bool,
+}
+
+pub trait SolverBackend {
+  /// Whether the conjunction of the constraints is satisfiable. Constraints
+  /// that cannot be encoded are ignored, and unknown results count as sat
+  fn check(&mut self, constraints: &[InternedConstraint]) -> bool;
+}
+
+pub struct AlwaysSatBackend;
+
+impl SolverBackend for AlwaysSatBackend {
+  fn check(&mut self, _: &[InternedConstraint]) -> bool {
+    true
+  }
+}
+
+#[cfg(feature = "z3")]
+thread_local! {
+  /// The Z3 context shared by all the solvers of a thread. It is leaked so
+  /// that a solver can be owned by the environment of a slice
+  static Z3_CONTEXT: &'static z3::Context = Box::leak(Box::new(z3::Context::new(&z3::Config::default())));
+}
+
+/// The Z3 context of the current thread
+#[cfg(feature = "z3")]
+pub fn thread_z3_context() -> &'static z3::Context {
+  Z3_CONTEXT.with(|z3_ctx| *z3_ctx)
+}
+
+/// Incremental Z3 solver. Each constraint is asserted only once, guarded by an
+/// assumption literal, and a query assumes the literals of its constraints
+#[cfg(feature = "z3")]
+pub struct Z3Backend {
+  z3_ctx: &'static z3::Context,
+  solver: z3::Solver<'static>,
+  symbol_map: HashMap<Value, z3::Symbol>,
+  symbol_id: u32,
+  literals: HashMap<usize, Option<z3::ast::Bool<'static>>>,
+}
+
+#[cfg(feature = "z3")]
+impl Z3Backend {
+  pub fn new() -> Self {
+    let z3_ctx = thread_z3_context();
+    Self {
+      z3_ctx,
+      solver: z3::Solver::new(z3_ctx),
+      symbol_map: HashMap::new(),
+      symbol_id: 0,
+      literals: HashMap::new(),
+    }
+  }
+
+  fn literal(&mut self, constraint: &InternedConstraint) -> Option<z3::ast::Bool<'static>> {
+    if let Some(literal) = self.literals.get(&constraint.id) {
+      return literal.clone();
+    }
+    let z3_cond = constraint
+      .cond
+      .into_z3_ast(&mut self.symbol_map, &mut self.symbol_id, self.z3_ctx);
+    let literal = z3_cond.map(|cond| {
+      let formula = if constraint.branch { cond } else { cond.not() };
+      let literal = z3::ast::Bool::new_const(self.z3_ctx, z3::Symbol::String(format!("c{}", constraint.id)));
+      self.solver.assert(&literal.implies(&formula));
+      literal
+    });
+    self.literals.insert(constraint.id, literal.clone());
+    literal
+  }
+}
+
+#[cfg(feature = "z3")]
+impl Default for Z3Backend {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(feature = "z3")]
+impl SolverBackend for Z3Backend {
+  fn check(&mut self, constraints: &[InternedConstraint]) -> bool {
+    let assumptions = constraints
+      .iter()
+      .filter_map(|constraint| self.literal(constraint))
+      .collect::<Vec<_>>();
+    match self.solver.check_assumptions(&assumptions) {
+      z3::SatResult::Sat | z3::SatResult::Unknown => true,
+      z3::SatResult::Unsat => false,
+    }
+  }
+}
+
+/// A running external solver. Its stdout is read by a separate thread so that
+/// queries can time out
+struct SmtLibProcess {
+  command: String,
+  child: Child,
+  stdin: ChildStdin,
+  lines: Receiver<String>,
+}
+
+thread_local! {
+  /// Solver processes left by the finished backends of the thread, so that
+  /// the slices executed by a thread share one process
+  static SMTLIB_PROCESSES: RefCell<Vec<SmtLibProcess>> = RefCell::new(vec![]);
+}
+
+impl SmtLibProcess {
+  fn spawn(command: &str) -> Result<Self, String> {
+    let mut args = command.split_whitespace();
+    let program = args.next().ok_or("Solver command cannot be empty".to_string())?;
+    let mut child = Command::new(program)
+      .args(args)
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .spawn()
+      .map_err(|_| format!("Cannot start solver {}", command))?;
+    let stdin = child.stdin.take().ok_or("Cannot open solver stdin".to_string())?;
+    let stdout = child.stdout.take().ok_or("Cannot open solver stdout".to_string())?;
+    let (sender, lines) = mpsc::channel();
+    thread::spawn(move || {
+      for line in BufReader::new(stdout).lines() {
+        let sent = line.ok().and_then(|line| sender.send(line).ok());
+        if sent.is_none() {
+          break;
+        }
+      }
+    });
+    let mut process = Self {
+      command: command.to_string(),
+      child,
+      stdin,
+      lines,
+    };
+    process
+      .send("(set-option :print-success false)\n")
+      .ok_or(format!("Cannot write to solver {}", command))?;
+    Ok(process)
+  }
+
+  /// Reuse a process of the thread running the command, or start a new one
+  fn take_or_spawn(command: &str) -> Result<Self, String> {
+    let pooled = SMTLIB_PROCESSES.with(|processes| {
+      let mut processes = processes.borrow_mut();
+      let index = processes.iter().position(|process| process.command == command)?;
+      Some(processes.swap_remove(index))
+    });
+    match pooled {
+      Some(process) => Ok(process),
+      None => Self::spawn(command),
+    }
+  }
+
+  /// Clear the declarations of the process and put it back to the pool
+  fn release(mut self) {
+    if self.send("(reset)\n(set-option :print-success false)\n").is_some() {
+      SMTLIB_PROCESSES.with(|processes| processes.borrow_mut().push(self));
+    }
+  }
+
+  fn send(&mut self, text: &str) -> Option<()> {
+    self.stdin.write_all(text.as_bytes()).ok()?;
+    self.stdin.flush().ok()
+  }
+
+  /// Send the query and wait for the result of its `check-sat`. Replies with
+  /// an `(error ...)` reject the query
+  fn check_sat(&mut self, query: &str, timeout: Duration) -> Option<bool> {
+    self.send(query)?;
+    let deadline = Instant::now() + timeout;
+    let mut has_error = false;
+    loop {
+      let remaining = deadline.checked_duration_since(Instant::now())?;
+      let line = self.lines.recv_timeout(remaining).ok()?;
+      match line.trim() {
+        "unsat" if !has_error => return Some(false),
+        "sat" | "unknown" if !has_error => return Some(true),
+        "sat" | "unsat" | "unknown" => return None,
+        line if line.starts_with("(error") => has_error = true,
+        _ => {}
+      }
+    }
+  }
+}
+
+impl Drop for SmtLibProcess {
+  fn drop(&mut self) {
+    let _ = self.send("(exit)\n");
+    let _ = self.child.kill();
+    let _ = self.child.wait();
+  }
+}
+
+/// External solver process, e.g. `z3 -in` or `cvc5 --lang smt2 --incremental`.
+/// Symbols are declared once at the outermost level and every query is
+/// wrapped in `push` & `pop`. The process is started on the first query, and
+/// restarted after a query fails or times out, which then counts as sat
+pub struct SmtLibBackend {
+  command: String,
+  timeout: Duration,
+  process: Option<SmtLibProcess>,
+  symbol_map: HashMap<Value, usize>,
+  num_declared_symbols: usize,
+}
+
+impl SmtLibBackend {
+  pub fn new(command: &str, timeout: Duration) -> Self {
+    Self {
+      command: command.to_string(),
+      timeout,
+      process: None,
+      symbol_map: HashMap::new(),
+      num_declared_symbols: 0,
+    }
+  }
+
+  /// Start the solver and check that it answers a trivial query
+  pub fn validate(command: &str, timeout: Duration) -> Result<(), String> {
+    let mut process = SmtLibProcess::take_or_spawn(command)?;
+    match process.check_sat("(push 1)\n(check-sat)\n(pop 1)\n", timeout) {
+      Some(true) => {
+        process.release();
+        Ok(())
+      }
+      _ => Err(format!("Solver {} does not answer SMT-LIB queries", command)),
+    }
+  }
+
+  fn query(&mut self, constraints: &[InternedConstraint]) -> Option<bool> {
+    if self.process.is_none() {
+      self.process = Some(SmtLibProcess::take_or_spawn(&self.command).ok()?);
+      self.num_declared_symbols = 0;
+    }
+    let assertions = constraints
+      .iter()
+      .filter_map(|constraint| {
+        let cond = constraint.cond.to_smtlib(&mut self.symbol_map)?;
+        if constraint.branch {
+          Some(format!("(assert {})", cond))
+        } else {
+          Some(format!("(assert (not {}))", cond))
+        }
+      })
+      .collect::<Vec<_>>();
+    let mut query = String::new();
+    for id in self.num_declared_symbols..self.symbol_map.len() {
+      query += &format!("(declare-const s{} Int)\n", id);
+    }
+    query += "(push 1)\n";
+    for assertion in assertions {
+      query += &format!("{}\n", assertion);
+    }
+    query += "(check-sat)\n(pop 1)\n";
+    let result = self.process.as_mut()?.check_sat(&query, self.timeout);
+    if result.is_some() {
+      self.num_declared_symbols = self.symbol_map.len();
+    } else {
+      // The state of the process is unknown, e.g. still solving, so it is dropped
+      self.process = None;
+    }
+    result
+  }
+}
+
+impl SolverBackend for SmtLibBackend {
+  fn check(&mut self, constraints: &[InternedConstraint]) -> bool {
+    self.query(constraints).unwrap_or(true)
+  }
+}
+
+impl Drop for SmtLibBackend {
+  fn drop(&mut self) {
+    if let Some(process) = self.process.take() {
+      process.release();
+    }
+  }
+}
+
+/// The solver shared by all the states of a slice. Constraints are interned
+/// and results are cached by the set of constraints so that the same path
+/// condition is never solved twice
+pub struct Solver {
+  backend: Box<dyn SolverBackend>,
+  constraint_ids: HashMap<(Comparison, bool), usize>,
+  constraints: Vec<(Comparison, bool)>,
+  cache: HashMap<Vec<usize>, bool>,
+}
+
+impl Solver {
+  /// The `command` and `timeout` are only used by the `smt-lib` solver
+  pub fn new(kind: SolverKind, command: &str, timeout: Duration) -> Result<Self, String> {
+    let backend: Box<dyn SolverBackend> = match kind {
+      #[cfg(feature = "z3")]
+      SolverKind::Z3 => Box::new(Z3Backend::new()),
+      #[cfg(not(feature = "z3"))]
+      SolverKind::Z3 => return Err("The analyzer is built without z3, use another solver".to_string()),
+      SolverKind::SmtLib => Box::new(SmtLibBackend::new(command, timeout)),
+      SolverKind::AlwaysSat => Box::new(AlwaysSatBackend),
+    };
+    Ok(Self::with_backend(backend))
+  }
+
+  /// Check once before the execution that the solver is available, so that
+  /// the slices do not fail one by one
+  pub fn validate(kind: SolverKind, command: &str, timeout: Duration) -> Result<(), String> {
+    Self::new(kind, command, timeout)?;
+    match kind {
+      SolverKind::SmtLib => SmtLibBackend::validate(command, timeout),
+      _ => Ok(()),
+    }
+  }
+
+  pub fn with_backend(backend: Box<dyn SolverBackend>) -> Self {
+    Self {
+      backend,
+      constraint_ids: HashMap::new(),
+      constraints: vec![],
+      cache: HashMap::new(),
+    }
+  }
+
+  /// Whether the conjunction of the constraints is satisfiable
+  pub fn sat<'a>(&mut self, constraints: impl IntoIterator<Item = &'a Constraint>) -> bool {
+    let ids = constraints
+      .into_iter()
+      .map(|constraint| self.constraint_id(&constraint.cond, constraint.branch))
+      .collect::<Vec<_>>();
+    self.check(ids)
+  }
+
+  /// Whether the constraints stay satisfiable after taking one more branch
+  pub fn sat_with<'a>(
+    &mut self,
+    constraints: impl IntoIterator<Item = &'a Constraint>,
+    cond: &Comparison,
+    branch: bool,
+  ) -> bool {
+    let mut ids = constraints
+      .into_iter()
+      .map(|constraint| self.constraint_id(&constraint.cond, constraint.branch))
+      .collect::<Vec<_>>();
+    ids.push(self.constraint_id(cond, branch));
+    self.check(ids)
+  }
+
+  fn check(&mut self, mut ids: Vec<usize>) -> bool {
+    ids.sort();
+    ids.dedup();
+    if let Some(result) = self.cache.get(&ids) {
+      return *result;
+    }
+    let interned = &self.constraints;
+    let constraints = ids
+      .iter()
+      .map(|id| InternedConstraint {
+        id: *id,
+        cond: &interned[*id].0,
+        branch: interned[*id].1,
+      })
+      .collect::<Vec<_>>();
+    let result = self.backend.check(&constraints);
+    self.cache.insert(ids, result);
+    result
+  }
+
+  fn constraint_id(&mut self, cond: &Comparison, branch: bool) -> usize {
+    let key = (cond.clone(), branch);
+    match self.constraint_ids.get(&key) {
+      Some(id) => *id,
+      None => {
+        let id = self.constraints.len();
+        self.constraints.push(key.clone());
+        self.constraint_ids.insert(key, id);
+        id
+      }
+    }
+  }
+}
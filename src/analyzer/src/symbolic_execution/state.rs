@@ -1,11 +1,11 @@
-use std::collections::HashMap;
-
+use im_rc::HashMap;
 use llir::values::*;
 
 use super::block_tracer::*;
 use super::constraints::*;
 use super::memory::*;
 use super::trace::*;
+use super::var_names::*;
 use crate::semantics::rced::*;
 use crate::slicer::*;
 
@@ -15,6 +15,10 @@ pub enum FinishState {
   BranchExplored,
   ExceedingMaxTraceLength,
   Unreachable,
+  PathUnsat,
+
+  /// Ended at a call that never returns, e.g. `exit`, `panic` or `BUG()`
+  Exited,
 }
 
 #[derive(Clone, Debug)]
@@ -24,12 +28,23 @@ pub struct State<'ctx> {
   pub block_trace_iter: BlockTraceIterator<'ctx>,
   pub visited_branch: VisitedBranch<'ctx>,
   pub trace: Trace<'ctx>,
-  pub target_node: Option<usize>,
+  /// Indices of the nodes of every call to the target, in order
+  pub target_nodes: Vec<usize>,
   pub statically_checked: bool,
   pub prev_block: Option<Block<'ctx>>,
   pub finish_state: FinishState,
   pub pointer_value_id_map: HashMap<GenericValue<'ctx>, usize>,
   pub constraints: Constraints,
+  pub var_names: VarNames<'ctx>,
+
+  /// Deepest recursion stepped into, 0 when no function calls itself
+  pub recursion_depth: usize,
+
+  /// Functions on the stack, outermost first
+  pub frames: Frames<'ctx>,
+
+  /// Functions on the stack at each node of the trace
+  pub node_frames: im_rc::Vector<Frames<'ctx>>,
 
   // Identifiers
   alloca_id: usize,
@@ -44,13 +59,17 @@ impl<'ctx> State<'ctx> {
       memory: Memory::new(),
       block_trace_iter: BlockTraceIterator::empty(),
       visited_branch: VisitedBranch::new(),
-      trace: Vec::new(),
-      target_node: None,
+      trace: Trace::new(),
+      target_nodes: Vec::new(),
       statically_checked: false,
       prev_block: None,
       finish_state: FinishState::ProperlyReturned,
       pointer_value_id_map: HashMap::new(),
-      constraints: Vec::new(),
+      constraints: Constraints::new(),
+      var_names: VarNames::default(),
+      recursion_depth: 0,
+      frames: Frames::unit(slice.entry),
+      node_frames: im_rc::Vector::new(),
       alloca_id: 0,
       symbol_id: 0,
       pointer_value_id: 0,
@@ -63,19 +82,34 @@ impl<'ctx> State<'ctx> {
       memory: Memory::new(),
       block_trace_iter: BlockTraceIterator::from_block_trace(block_trace),
       visited_branch: VisitedBranch::new(),
-      trace: Vec::new(),
-      target_node: None,
+      trace: Trace::new(),
+      target_nodes: Vec::new(),
       statically_checked: false,
       prev_block: None,
       finish_state: FinishState::ProperlyReturned,
       pointer_value_id_map: HashMap::new(),
-      constraints: Vec::new(),
+      constraints: Constraints::new(),
+      var_names: VarNames::default(),
+      recursion_depth: 0,
+      frames: Frames::unit(slice.entry),
+      node_frames: im_rc::Vector::new(),
       alloca_id: 0,
       symbol_id: 0,
       pointer_value_id: 0,
     }
   }
 
+  /// Append the node to the trace, under the functions on the stack
+  pub fn push_node(&mut self, node: TraceNode<'ctx>) {
+    self.node_frames.push_back(self.frames.clone());
+    self.trace.push_back(node);
+  }
+
+  /// The first call to the target
+  pub fn target_node(&self) -> Option<usize> {
+    self.target_nodes.first().cloned()
+  }
+
   pub fn new_alloca_id(&mut self) -> usize {
     let result = self.alloca_id;
     self.alloca_id += 1;
@@ -88,7 +122,14 @@ impl<'ctx> State<'ctx> {
     result
   }
 
-  pub fn add_constraint(&mut self, cond: Comparison, branch: bool) {
-    self.constraints.push(Constraint { cond, branch });
+  /// Add the constraint of taking the branch of the instruction, which is
+  /// pushed to the trace right after
+  pub fn add_constraint(&mut self, instr: Instruction<'ctx>, cond: Comparison, branch: bool) {
+    self.constraints.push_back(Constraint {
+      cond,
+      branch,
+      loc: instr.debug_loc_string(),
+      node_id: self.trace.len(),
+    });
   }
 }
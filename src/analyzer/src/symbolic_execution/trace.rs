@@ -1,9 +1,13 @@
 // use std::collections::HashSet;
 use llir::values::*;
 use serde_json::json;
+use std::collections::HashMap;
 use std::rc::Rc;
 
-use crate::semantics::rced::*;
+use super::constraints::*;
+use super::state::*;
+use crate::semantics::{rced::*, TypeInfo};
+use crate::utils::*;
 
 #[derive(Clone, Debug)]
 pub struct TraceNode<'ctx> {
@@ -12,36 +16,129 @@ pub struct TraceNode<'ctx> {
   pub result: Option<Rc<Value>>,
 }
 
-pub type Trace<'ctx> = Vec<TraceNode<'ctx>>;
+impl<'ctx> TraceNode<'ctx> {
+  /// Type of the result, if the node has one
+  pub fn result_type(&self) -> Option<TypeInfo> {
+    self.result.as_ref().map(|_| TypeInfo::from_type(self.instr.get_type()))
+  }
+
+  /// Types of the arguments of a call node, empty for other nodes
+  pub fn arg_types(&self) -> Vec<TypeInfo> {
+    match self.instr.as_call_like() {
+      Some(call) => call
+        .arguments()
+        .into_iter()
+        .map(|arg| TypeInfo::from_type(arg.get_type()))
+        .collect(),
+      None => vec![],
+    }
+  }
+}
+
+/// Nodes are shared between the traces forked from a common prefix
+pub type Trace<'ctx> = im_rc::Vector<TraceNode<'ctx>>;
+
+/// Functions on the stack, outermost first, shared between the nodes
+/// executed under them
+pub type Frames<'ctx> = im_rc::Vector<Function<'ctx>>;
 
 pub struct TraceWithTarget<'ctx> {
   pub trace: Trace<'ctx>,
+  /// The first call to the target
   pub target_index: usize,
+
+  /// Every call to the target, in order
+  pub target_indices: Vec<usize>,
   pub statically_checked: bool,
+
+  /// How the execution of the trace finished
+  pub finish_state: FinishState,
+  pub constraints: Constraints,
+
+  /// Source-level names of the values & locations in the trace
+  pub var_names: HashMap<Rc<Value>, String>,
+
+  /// Deepest recursion the trace steps into
+  pub recursion_depth: usize,
+
+  /// Functions on the stack at each node
+  pub frames: im_rc::Vector<Frames<'ctx>>,
 }
 
 impl<'ctx> TraceWithTarget<'ctx> {
-  pub fn new(trace: Trace<'ctx>, target_index: usize, statically_checked: bool) -> Self {
-    Self { trace, target_index, statically_checked }
+  pub fn new(
+    trace: Trace<'ctx>,
+    target_indices: Vec<usize>,
+    statically_checked: bool,
+    finish_state: FinishState,
+    constraints: Constraints,
+    var_names: HashMap<Rc<Value>, String>,
+    recursion_depth: usize,
+    frames: im_rc::Vector<Frames<'ctx>>,
+  ) -> Self {
+    Self {
+      trace,
+      target_index: target_indices[0],
+      target_indices,
+      statically_checked,
+      finish_state,
+      constraints,
+      var_names,
+      recursion_depth,
+      frames,
+    }
   }
 
   pub fn target(&self) -> &TraceNode<'ctx> {
     &self.trace[self.target_index]
   }
 
+  /// Whether the trace ends at a call that never returns
+  pub fn exited(&self) -> bool {
+    match self.finish_state {
+      FinishState::Exited => true,
+      _ => false,
+    }
+  }
+
+  /// Why the trace is cut before returning, for kept truncated traces
+  pub fn truncation_reason(&self) -> Option<&'static str> {
+    match self.finish_state {
+      FinishState::BranchExplored => Some("branch_explored"),
+      FinishState::ExceedingMaxTraceLength => Some("exceeding_max_length"),
+      _ => None,
+    }
+  }
+
   pub fn reduce(self) -> Self {
     self
   }
 
   pub fn to_json(&self) -> serde_json::Value {
+    let mut var_names = self
+      .var_names
+      .iter()
+      .map(|(value, name)| json!({ "value": value, "name": name }))
+      .collect::<Vec<_>>();
+    var_names.sort_by_key(|var_name| var_name["name"].to_string());
     json!({
-      "instrs": self.trace.iter().map(|node| json!({
+      "instrs": self.trace.iter().zip(&self.frames).map(|(node, frames)| json!({
         "loc": node.instr.debug_loc_string(),
+        "debug_loc": node.instr.debug_location(),
+        "frames": frames.iter().map(|f| f.simp_name()).collect::<Vec<_>>(),
         "sem": node.semantics,
-        "res": node.result
+        "res": node.result,
+        "ty": node.result_type(),
+        "arg_tys": node.arg_types(),
       })).collect::<Vec<_>>(),
       "target": self.target_index,
+      "targets": self.target_indices,
       "statically_checked": self.statically_checked,
+      "exited": self.exited(),
+      "truncated": self.truncation_reason(),
+      "recursion_depth": self.recursion_depth,
+      "constraints": self.constraints,
+      "names": var_names,
     })
   }
 
@@ -58,11 +155,20 @@ impl<'ctx> TraceWithTarget<'ctx> {
 
   pub fn print(&self) {
     for (i, node) in self.trace.iter().enumerate() {
-      if i == self.target_index {
+      if self.target_indices.contains(&i) {
         print!("-> TARGET ");
       }
       match &node.result {
-        Some(result) => println!("{} {:?} -> {:?}", node.instr.debug_loc_string(), node.semantics, result),
+        Some(result) => match self.var_names.get(result) {
+          Some(name) => println!(
+            "{} {:?} -> {:?} ({})",
+            node.instr.debug_loc_string(),
+            node.semantics,
+            result,
+            name
+          ),
+          None => println!("{} {:?} -> {:?}", node.instr.debug_loc_string(), node.semantics, result),
+        },
         None => println!("{} {:?}", node.instr.debug_loc_string(), node.semantics),
       }
     }
@@ -0,0 +1,156 @@
+use llir::values::*;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::semantics::rced::*;
+use crate::utils::*;
+
+/// Source-level name of a value, or of the variable stored at a location,
+/// e.g. `dev->priv->lock`
+#[derive(Clone, Debug)]
+pub struct VarName<'ctx> {
+  pub path: String,
+  pub ty: Option<DebugType<'ctx>>,
+}
+
+impl<'ctx> From<DebugVariable<'ctx>> for VarName<'ctx> {
+  fn from(var: DebugVariable<'ctx>) -> Self {
+    Self {
+      path: var.name,
+      ty: var.ty,
+    }
+  }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct VarNames<'ctx> {
+  /// Local variables declared by `llvm.dbg.declare`, keyed by their alloca
+  allocas: im_rc::HashMap<Instruction<'ctx>, VarName<'ctx>>,
+
+  /// Names of the values held by variables
+  values: im_rc::HashMap<Rc<Value>, VarName<'ctx>>,
+
+  /// Names of the variables or fields stored at locations computed by GEPs
+  locations: im_rc::HashMap<Rc<Value>, VarName<'ctx>>,
+}
+
+impl<'ctx> VarNames<'ctx> {
+  pub fn declare(&mut self, alloca: Instruction<'ctx>, var: DebugVariable<'ctx>) {
+    self.allocas.insert(alloca, var.into());
+  }
+
+  /// Name the value, keeping the first name of a value that is held by
+  /// several variables. Constants are never named
+  pub fn name_value(&mut self, value: Rc<Value>, name: VarName<'ctx>) {
+    match &*value {
+      Value::Int(_) | Value::Null | Value::Unknown | Value::Func(_) | Value::FuncPtr | Value::Asm => {}
+      _ => {
+        self.values.entry(value).or_insert(name);
+      }
+    }
+  }
+
+  pub fn value_name(&self, value: &Rc<Value>) -> Option<&VarName<'ctx>> {
+    self.values.get(value)
+  }
+
+  pub fn location_name(&self, loc_operand: Operand<'ctx>, loc: &Rc<Value>) -> Option<&VarName<'ctx>> {
+    match loc_operand {
+      Operand::Instruction(instr @ Instruction::Alloca(_)) => self.allocas.get(&instr),
+      _ => self.locations.get(loc),
+    }
+  }
+
+  /// The value loaded from or stored to a named location takes its name
+  pub fn access(&mut self, loc_operand: Operand<'ctx>, loc: &Rc<Value>, value: &Rc<Value>) {
+    if let Some(name) = self.location_name(loc_operand, loc).cloned() {
+      self.name_value(value.clone(), name);
+    }
+  }
+
+  /// Name the location computed by a GEP after the variable or the pointer it
+  /// starts from and the fields it goes through, which are found by the
+  /// offsets of the struct indices
+  pub fn gep(
+    &mut self,
+    instr: GetElementPtrInstruction<'ctx>,
+    loc: &Rc<Value>,
+    indices: &[Rc<Value>],
+    res: &Rc<Value>,
+  ) {
+    if let Some(name) = self.gep_name(instr.location(), loc, indices, &instr.field_offsets()) {
+      self.locations.insert(res.clone(), name);
+    }
+  }
+
+  fn gep_name(
+    &self,
+    loc_operand: Operand<'ctx>,
+    loc: &Rc<Value>,
+    indices: &[Rc<Value>],
+    field_offsets: &[Option<u64>],
+  ) -> Option<VarName<'ctx>> {
+    let (first, rest) = indices.split_first()?;
+
+    // The lvalue the first index points to, and the pointer it is accessed through
+    let (mut lvalue, mut ptr, mut ty) = match (self.location_name(loc_operand, loc), self.value_name(loc)) {
+      (Some(var), _) if **first == Value::Int(0) => (var.path.clone(), None, var.ty),
+      (None, Some(ptr)) if **first == Value::Int(0) => (
+        format!("*{}", ptr.path),
+        Some(ptr.path.clone()),
+        ptr.ty.and_then(|ty| ty.pointee()),
+      ),
+      (None, Some(ptr)) => (
+        format!("{}[{}]", ptr.path, self.index_name(first)),
+        None,
+        ptr.ty.and_then(|ty| ty.pointee()),
+      ),
+      _ => return None,
+    };
+
+    for (index, field_offset) in rest.iter().zip(field_offsets) {
+      let curr_ty = ty?;
+      match curr_ty.element() {
+        Some(element) => {
+          if ptr.is_some() {
+            lvalue = format!("({})", lvalue);
+          }
+          lvalue = format!("{}[{}]", lvalue, self.index_name(index));
+          ty = Some(element);
+        }
+        None => {
+          let (field, field_ty) = curr_ty.field_at_offset((*field_offset)?)?;
+          lvalue = match ptr {
+            Some(ptr) => format!("{}->{}", ptr, field),
+            None => format!("{}.{}", lvalue, field),
+          };
+          ty = Some(field_ty);
+        }
+      }
+      ptr = None;
+    }
+
+    Some(VarName { path: lvalue, ty })
+  }
+
+  fn index_name(&self, index: &Rc<Value>) -> String {
+    match (&**index, self.value_name(index)) {
+      (Value::Int(i), _) => i.to_string(),
+      (_, Some(name)) => name.path.clone(),
+      _ => "?".to_string(),
+    }
+  }
+
+  /// Names of the values, and of the locations prefixed by `&`
+  pub fn names(&self) -> HashMap<Rc<Value>, String> {
+    let values = self
+      .values
+      .iter()
+      .map(|(value, name)| (value.clone(), name.path.clone()));
+    let locations = self
+      .locations
+      .iter()
+      .map(|(loc, name)| (loc.clone(), format!("&{}", name.path)));
+    values.chain(locations).collect()
+  }
+}
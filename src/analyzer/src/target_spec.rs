@@ -0,0 +1,454 @@
+//! Target specification file
+//!
+//! A TOML or JSON file listing the APIs to analyze. Each target is matched by
+//! name, regex or signature pattern, and can override slicer & symbolic
+//! execution options. For example
+//!
+//! ```toml
+//! [[targets]]
+//! name = "kmalloc"
+//! options = { slice_depth = 2, max_trace_per_slice = 100 }
+//!
+//! [[targets]]
+//! signature = { returns = "pointer", args = ["struct device *"] }
+//! ```
+//!
+//! The overrides apply to a whole target, i.e. to all the call edges of the
+//! functions sharing its simplified name. When these functions match different
+//! entries, the first of them in the spec is used.
+
+use llir::values::*;
+use regex::Regex;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::options::*;
+use crate::slicer::*;
+use crate::symbolic_execution::*;
+use crate::utils::*;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TargetOptionOverrides {
+  pub slice_depth: Option<usize>,
+  pub max_slice_depth: Option<usize>,
+  pub max_avg_num_blocks: Option<usize>,
+  pub max_entries_per_edge: Option<usize>,
+  pub max_edges_per_target: Option<usize>,
+  pub max_work: Option<usize>,
+  pub loop_unroll_bound: Option<usize>,
+  pub max_recursion_depth: Option<usize>,
+  pub max_node_per_trace: Option<usize>,
+  pub max_explored_trace_per_slice: Option<usize>,
+  pub max_trace_per_slice: Option<usize>,
+}
+
+/// Pattern on function signatures. Type patterns are either `*` (any type),
+/// `pointer`, `integer`, or a C-like type such as `struct device *` or `i32`
+#[derive(Debug, Clone, Deserialize)]
+pub struct SignaturePattern {
+  pub returns: Option<String>,
+
+  /// Patterns of the leading arguments
+  #[serde(default)]
+  pub args: Vec<String>,
+
+  pub num_args: Option<usize>,
+}
+
+impl SignaturePattern {
+  pub fn matches(&self, func: &Function) -> bool {
+    let func_type = func.get_function_type();
+    let arg_types = func_type.argument_types();
+    let returns_match = match &self.returns {
+      Some(pattern) => type_matches(pattern, &func_type.return_type()),
+      None => true,
+    };
+    let num_args_match = match self.num_args {
+      Some(num_args) => num_args == arg_types.len(),
+      None => true,
+    };
+    let args_match = self.args.len() <= arg_types.len()
+      && self
+        .args
+        .iter()
+        .zip(arg_types.iter())
+        .all(|(pattern, ty)| type_matches(pattern, ty));
+    returns_match && num_args_match && args_match
+  }
+}
+
+fn type_matches(pattern: &str, ty: &llir::types::Type) -> bool {
+  use llir::types::Type;
+  match (pattern.trim(), ty) {
+    ("*", _) => true,
+    ("pointer", Type::Pointer(_)) => true,
+    ("integer", Type::Int(_)) => true,
+    (pattern, ty) => {
+      let normalize = |s: &str| s.split_whitespace().collect::<String>();
+      normalize(pattern) == normalize(ty.description().as_str())
+    }
+  }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawTargetSpecEntry {
+  name: Option<String>,
+  regex: Option<String>,
+  signature: Option<SignaturePattern>,
+  #[serde(default)]
+  options: TargetOptionOverrides,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawTargetSpec {
+  #[serde(default)]
+  targets: Vec<RawTargetSpecEntry>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TargetSpecEntry {
+  pub name: Option<String>,
+  pub regex: Option<Regex>,
+  pub signature: Option<SignaturePattern>,
+  pub options: TargetOptionOverrides,
+}
+
+impl TargetSpecEntry {
+  /// Every matcher provided by the entry needs to match the function
+  pub fn matches(&self, func: &Function) -> bool {
+    let name = func.simp_name();
+    let name_match = match &self.name {
+      Some(n) => n == &name,
+      None => true,
+    };
+    let regex_match = match &self.regex {
+      Some(r) => r.is_match(name.as_str()),
+      None => true,
+    };
+    let signature_match = match &self.signature {
+      Some(s) => s.matches(func),
+      None => true,
+    };
+    name_match && regex_match && signature_match
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct TargetSpec {
+  pub targets: Vec<TargetSpecEntry>,
+}
+
+impl TargetSpec {
+  /// Parse the spec from a TOML or JSON string
+  pub fn parse(text: &str, is_json: bool) -> Result<Self, String> {
+    let raw: RawTargetSpec = if is_json {
+      serde_json::from_str(text).map_err(|err| format!("Cannot parse target spec: {}", err))?
+    } else {
+      toml::from_str(text).map_err(|err| format!("Cannot parse target spec: {}", err))?
+    };
+    let targets = raw
+      .targets
+      .into_iter()
+      .map(|entry| {
+        if entry.name.is_none() && entry.regex.is_none() && entry.signature.is_none() {
+          return Err("Target spec entry must provide name, regex or signature".to_string());
+        }
+        let regex = match entry.regex {
+          Some(r) => Some(Regex::new(r.as_str()).map_err(|_| format!("Cannot parse target regex {}", r))?),
+          None => None,
+        };
+        Ok(TargetSpecEntry {
+          name: entry.name,
+          regex,
+          signature: entry.signature,
+          options: entry.options,
+        })
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+    Ok(Self { targets })
+  }
+
+  /// Load the spec from a file. Files ending with `.json` are parsed as JSON,
+  /// all the others as TOML
+  pub fn load(path: &Path) -> Result<Self, String> {
+    let text = fs::read_to_string(path).map_err(|_| format!("Cannot read target spec {:?}", path))?;
+    let is_json = path.extension().map(|ext| ext == "json").unwrap_or(false);
+    Self::parse(text.as_str(), is_json)
+  }
+
+  pub fn from_path(path: &str) -> Result<Self, String> {
+    Self::load(Path::new(path))
+  }
+
+  /// The first entry matching the function
+  pub fn entry_of(&self, func: &Function) -> Option<&TargetSpecEntry> {
+    self.targets.iter().find(|entry| entry.matches(func))
+  }
+
+  pub fn matches(&self, func: &Function) -> bool {
+    self.entry_of(func).is_some()
+  }
+
+  pub fn overrides_of(&self, func: &Function) -> Option<&TargetOptionOverrides> {
+    self.entry_of(func).map(|entry| &entry.options)
+  }
+
+  /// The overrides of the first entry matching any of the callees of a target
+  pub fn overrides_of_target<'ctx, I>(&self, callees: I) -> Option<&TargetOptionOverrides>
+  where
+    I: IntoIterator<Item = Function<'ctx>>,
+  {
+    let callees = callees.into_iter().collect::<Vec<_>>();
+    self
+      .targets
+      .iter()
+      .find(|entry| callees.iter().any(|callee| entry.matches(callee)))
+      .map(|entry| &entry.options)
+  }
+}
+
+/// Options of a single target: the global options with the overrides from
+/// the target spec applied
+pub struct TargetOptions<'a, O> {
+  pub options: &'a O,
+  pub overrides: &'a TargetOptionOverrides,
+}
+
+impl<'a, O> TargetOptions<'a, O> {
+  pub fn new(options: &'a O, overrides: &'a TargetOptionOverrides) -> Self {
+    Self { options, overrides }
+  }
+}
+
+impl<'a, O> GeneralOptions for TargetOptions<'a, O>
+where
+  O: GeneralOptions,
+{
+  fn use_serial(&self) -> bool {
+    self.options.use_serial()
+  }
+
+  fn seed(&self) -> u64 {
+    self.options.seed()
+  }
+}
+
+impl<'a, O> IOOptions for TargetOptions<'a, O>
+where
+  O: IOOptions,
+{
+  fn input_path(&self) -> PathBuf {
+    self.options.input_path()
+  }
+
+  fn output_path(&self) -> PathBuf {
+    self.options.output_path()
+  }
+
+  fn default_package(&self) -> Option<&str> {
+    self.options.default_package()
+  }
+}
+
+impl<'a, O> SlicerOptions for TargetOptions<'a, O>
+where
+  O: SlicerOptions,
+{
+  fn no_reduce_slice(&self) -> bool {
+    self.options.no_reduce_slice()
+  }
+
+  fn slice_depth(&self) -> usize {
+    self
+      .overrides
+      .slice_depth
+      .unwrap_or(SlicerOptions::slice_depth(self.options))
+  }
+
+  fn adaptive_slice_depth(&self) -> bool {
+    self.options.adaptive_slice_depth()
+  }
+
+  fn max_slice_depth(&self) -> usize {
+    self.overrides.max_slice_depth.unwrap_or(self.options.max_slice_depth())
+  }
+
+  fn entry_filter(&self) -> &Option<String> {
+    self.options.entry_filter()
+  }
+
+  fn entry_policies(&self) -> &Vec<EntryPolicy> {
+    self.options.entry_policies()
+  }
+
+  fn entry_patterns(&self) -> &Option<String> {
+    self.options.entry_patterns()
+  }
+
+  fn stop_at_entries(&self) -> bool {
+    self.options.stop_at_entries()
+  }
+
+  fn max_entry_climb(&self) -> usize {
+    self.options.max_entry_climb()
+  }
+
+  fn max_entries_per_edge(&self) -> Option<usize> {
+    self
+      .overrides
+      .max_entries_per_edge
+      .or(self.options.max_entries_per_edge())
+  }
+
+  fn target_inclusion_filter(&self) -> &Option<String> {
+    self.options.target_inclusion_filter()
+  }
+
+  fn target_exclusion_filter(&self) -> &Option<String> {
+    self.options.target_exclusion_filter()
+  }
+
+  fn target_spec(&self) -> &Option<TargetSpec> {
+    SlicerOptions::target_spec(self.options)
+  }
+
+  fn use_regex_filter(&self) -> bool {
+    self.options.use_regex_filter()
+  }
+
+  fn max_avg_num_blocks(&self) -> usize {
+    self
+      .overrides
+      .max_avg_num_blocks
+      .unwrap_or(self.options.max_avg_num_blocks())
+  }
+
+  fn max_edges_per_target(&self) -> Option<usize> {
+    self
+      .overrides
+      .max_edges_per_target
+      .or(self.options.max_edges_per_target())
+  }
+
+  fn sampling_stratum(&self) -> SamplingStratum {
+    self.options.sampling_stratum()
+  }
+
+  fn relatedness_policies(&self) -> &Vec<RelatednessPolicy> {
+    self.options.relatedness_policies()
+  }
+
+  fn relatedness_prefix_length(&self) -> usize {
+    self.options.relatedness_prefix_length()
+  }
+
+  fn dump_reduction_report(&self) -> bool {
+    self.options.dump_reduction_report()
+  }
+}
+
+impl<'a, O> SymbolicExecutionOptions for TargetOptions<'a, O>
+where
+  O: SymbolicExecutionOptions,
+{
+  fn slice_depth(&self) -> usize {
+    self
+      .overrides
+      .slice_depth
+      .unwrap_or(SymbolicExecutionOptions::slice_depth(self.options))
+  }
+
+  fn max_work(&self) -> usize {
+    self.overrides.max_work.unwrap_or(self.options.max_work())
+  }
+
+  fn loop_unroll_bound(&self) -> usize {
+    self
+      .overrides
+      .loop_unroll_bound
+      .unwrap_or(self.options.loop_unroll_bound())
+  }
+
+  fn max_recursion_depth(&self) -> usize {
+    self
+      .overrides
+      .max_recursion_depth
+      .unwrap_or(self.options.max_recursion_depth())
+  }
+
+  fn no_random_work(&self) -> bool {
+    self.options.no_random_work()
+  }
+
+  fn max_node_per_trace(&self) -> usize {
+    self
+      .overrides
+      .max_node_per_trace
+      .unwrap_or(self.options.max_node_per_trace())
+  }
+
+  fn max_explored_trace_per_slice(&self) -> usize {
+    self
+      .overrides
+      .max_explored_trace_per_slice
+      .unwrap_or(self.options.max_explored_trace_per_slice())
+  }
+
+  fn max_trace_per_slice(&self) -> usize {
+    self
+      .overrides
+      .max_trace_per_slice
+      .unwrap_or(self.options.max_trace_per_slice())
+  }
+
+  fn no_trace_reduction(&self) -> bool {
+    self.options.no_trace_reduction()
+  }
+
+  fn no_prefilter_block_trace(&self) -> bool {
+    self.options.no_prefilter_block_trace()
+  }
+
+  fn keep_truncated_traces(&self) -> bool {
+    self.options.keep_truncated_traces()
+  }
+
+  fn post_target_guidance(&self) -> bool {
+    self.options.post_target_guidance()
+  }
+
+  fn no_prune_infeasible_branch(&self) -> bool {
+    self.options.no_prune_infeasible_branch()
+  }
+
+  fn solver(&self) -> SolverKind {
+    self.options.solver()
+  }
+
+  fn solver_command(&self) -> &str {
+    self.options.solver_command()
+  }
+
+  fn solver_timeout(&self) -> u64 {
+    self.options.solver_timeout()
+  }
+
+  fn print_block_trace(&self) -> bool {
+    self.options.print_block_trace()
+  }
+
+  fn print_trace(&self) -> bool {
+    self.options.print_trace()
+  }
+
+  fn dump_smt(&self) -> bool {
+    self.options.dump_smt()
+  }
+
+  fn target_spec(&self) -> &Option<TargetSpec> {
+    SymbolicExecutionOptions::target_spec(self.options)
+  }
+}
@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 pub fn cartesian(v: &Vec<usize>) -> Vec<Vec<usize>> {
   if v.len() == 0 {
     vec![]
@@ -65,3 +67,88 @@ pub fn cartesian_two<T: Copy + Clone>(l1: &Vec<Vec<T>>, l2: &Vec<Vec<T>>) -> Vec
   }
   res
 }
+
+/// Lazy cartesian product of the ranges `0..sizes[i]`, in lexicographic order
+#[derive(Debug, Clone)]
+pub struct CartesianIterator {
+  sizes: Vec<usize>,
+  next: Option<Vec<usize>>,
+}
+
+impl CartesianIterator {
+  pub fn new(sizes: Vec<usize>) -> Self {
+    let next = if sizes.is_empty() || sizes.contains(&0) {
+      None
+    } else {
+      Some(vec![0; sizes.len()])
+    };
+    Self { sizes, next }
+  }
+}
+
+impl Iterator for CartesianIterator {
+  type Item = Vec<usize>;
+
+  fn next(&mut self) -> Option<Vec<usize>> {
+    let curr = self.next.take()?;
+    let mut next = curr.clone();
+    for i in (0..next.len()).rev() {
+      next[i] += 1;
+      if next[i] < self.sizes[i] {
+        self.next = Some(next);
+        break;
+      }
+      next[i] = 0;
+    }
+    Some(curr)
+  }
+}
+
+/// Lazy cartesian product that first goes through the "diagonal"
+/// `[k % sizes[0], k % sizes[1], ...]` for `k < max(sizes)`, so that every
+/// index of every dimension appears within the first `max(sizes)` elements.
+/// The rest of the product follows in lexicographic order
+#[derive(Debug, Clone)]
+pub struct DiagonalCartesianIterator {
+  diagonal: Vec<Vec<usize>>,
+  diagonal_set: HashSet<Vec<usize>>,
+  diagonal_id: usize,
+  rest: CartesianIterator,
+}
+
+impl DiagonalCartesianIterator {
+  pub fn new(sizes: Vec<usize>) -> Self {
+    let rest = CartesianIterator::new(sizes.clone());
+    let mut diagonal: Vec<Vec<usize>> = vec![];
+    let mut diagonal_set = HashSet::new();
+    if rest.next.is_some() {
+      let max_size = sizes.iter().cloned().max().unwrap_or(0);
+      for k in 0..max_size {
+        let indices = sizes.iter().map(|size| k % size).collect::<Vec<_>>();
+        if diagonal_set.insert(indices.clone()) {
+          diagonal.push(indices);
+        }
+      }
+    }
+    Self {
+      diagonal,
+      diagonal_set,
+      diagonal_id: 0,
+      rest,
+    }
+  }
+}
+
+impl Iterator for DiagonalCartesianIterator {
+  type Item = Vec<usize>;
+
+  fn next(&mut self) -> Option<Vec<usize>> {
+    if self.diagonal_id < self.diagonal.len() {
+      self.diagonal_id += 1;
+      Some(self.diagonal[self.diagonal_id - 1].clone())
+    } else {
+      let diagonal_set = &self.diagonal_set;
+      self.rest.find(|indices| !diagonal_set.contains(indices))
+    }
+  }
+}
@@ -0,0 +1,346 @@
+use llir::values::*;
+use llvm_sys::core::*;
+use llvm_sys::debuginfo::*;
+use llvm_sys::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::ffi::CStr;
+use std::marker::PhantomData;
+
+/// Operand of a metadata node, i.e. the value wrapped by a `ValueAsMetadata`
+/// or a `MetadataAsValue` of the metadata operand. Missing operands are null
+fn md_operand(md: LLVMValueRef, index: usize) -> Option<LLVMValueRef> {
+  unsafe {
+    if md.is_null() || LLVMIsAMDNode(md).is_null() {
+      return None;
+    }
+    let num_operands = LLVMGetMDNodeNumOperands(md) as usize;
+    if index >= num_operands {
+      return None;
+    }
+    let mut operands = vec![std::ptr::null_mut(); num_operands];
+    LLVMGetMDNodeOperands(md, operands.as_mut_ptr());
+    let operand = operands[index];
+    if operand.is_null() {
+      None
+    } else {
+      Some(operand)
+    }
+  }
+}
+
+fn md_string(md: LLVMValueRef) -> Option<String> {
+  unsafe {
+    let mut len = 0;
+    let ptr = LLVMGetMDString(md, &mut len);
+    if ptr.is_null() {
+      None
+    } else {
+      let bytes = std::slice::from_raw_parts(ptr as *const u8, len as usize);
+      Some(String::from_utf8_lossy(bytes).to_string())
+    }
+  }
+}
+
+/// The DWARF tags of the derived & composite types told apart
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum DebugTag {
+  PointerType,
+  Typedef,
+  ConstType,
+  VolatileType,
+  RestrictType,
+  AtomicType,
+  ArrayType,
+  UnionType,
+  Member,
+  Inheritance,
+  Other,
+}
+
+impl DebugTag {
+  /// The tag of a type node. The C API has no getter for it, so it is read
+  /// from the printed node
+  fn of(md: LLVMValueRef) -> Self {
+    let kind = unsafe { LLVMGetMetadataKind(LLVMValueAsMetadata(md)) };
+    match kind {
+      LLVMMetadataKind::LLVMDIDerivedTypeMetadataKind | LLVMMetadataKind::LLVMDICompositeTypeMetadataKind => {}
+      _ => return Self::Other,
+    }
+    let printed = unsafe {
+      let ptr = LLVMPrintValueToString(md);
+      let printed = CStr::from_ptr(ptr).to_string_lossy().to_string();
+      LLVMDisposeMessage(ptr);
+      printed
+    };
+    let tag = match printed.find("tag: DW_TAG_") {
+      Some(start) => &printed[start + "tag: ".len()..],
+      None => return Self::Other,
+    };
+    let end = tag
+      .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+      .unwrap_or(tag.len());
+    match &tag[..end] {
+      "DW_TAG_pointer_type" => Self::PointerType,
+      "DW_TAG_typedef" => Self::Typedef,
+      "DW_TAG_const_type" => Self::ConstType,
+      "DW_TAG_volatile_type" => Self::VolatileType,
+      "DW_TAG_restrict_type" => Self::RestrictType,
+      "DW_TAG_atomic_type" => Self::AtomicType,
+      "DW_TAG_array_type" => Self::ArrayType,
+      "DW_TAG_union_type" => Self::UnionType,
+      "DW_TAG_member" => Self::Member,
+      "DW_TAG_inheritance" => Self::Inheritance,
+      _ => Self::Other,
+    }
+  }
+}
+
+/// A DWARF type, e.g. `struct device *`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DebugType<'ctx> {
+  md: LLVMValueRef,
+  tag: DebugTag,
+  marker: PhantomData<&'ctx ()>,
+}
+
+impl<'ctx> DebugType<'ctx> {
+  fn new(md: LLVMValueRef) -> Self {
+    Self {
+      md,
+      tag: DebugTag::of(md),
+      marker: PhantomData,
+    }
+  }
+
+  fn kind(&self) -> LLVMMetadataKind {
+    unsafe { LLVMGetMetadataKind(LLVMValueAsMetadata(self.md)) }
+  }
+
+  fn is_derived(&self) -> bool {
+    self.kind() == LLVMMetadataKind::LLVMDIDerivedTypeMetadataKind
+  }
+
+  fn is_composite(&self) -> bool {
+    self.kind() == LLVMMetadataKind::LLVMDICompositeTypeMetadataKind
+  }
+
+  pub fn name(&self) -> Option<String> {
+    unsafe {
+      let mut len = 0;
+      let ptr = LLVMDITypeGetName(LLVMValueAsMetadata(self.md), &mut len);
+      if ptr.is_null() || len == 0 {
+        None
+      } else {
+        let bytes = std::slice::from_raw_parts(ptr as *const u8, len);
+        Some(String::from_utf8_lossy(bytes).to_string())
+      }
+    }
+  }
+
+  fn offset_in_bits(&self) -> u64 {
+    unsafe { LLVMDITypeGetOffsetInBits(LLVMValueAsMetadata(self.md)) }
+  }
+
+  fn flags(&self) -> LLVMDIFlags {
+    unsafe { LLVMDITypeGetFlags(LLVMValueAsMetadata(self.md)) }
+  }
+
+  /// The type referred to by a derived type, or the element type of an array
+  fn base_type(&self) -> Option<Self> {
+    md_operand(self.md, 3).map(Self::new)
+  }
+
+  pub fn is_pointer(&self) -> bool {
+    self.strip().tag == DebugTag::PointerType
+  }
+
+  /// The type with typedefs and qualifiers removed
+  pub fn strip(&self) -> Self {
+    let mut ty = *self;
+    while ty.is_derived() {
+      match ty.tag {
+        DebugTag::Typedef
+        | DebugTag::ConstType
+        | DebugTag::VolatileType
+        | DebugTag::RestrictType
+        | DebugTag::AtomicType => match ty.base_type() {
+          Some(base) => ty = base,
+          None => break,
+        },
+        _ => break,
+      }
+    }
+    ty
+  }
+
+  pub fn pointee(&self) -> Option<Self> {
+    if self.is_pointer() {
+      self.strip().base_type()
+    } else {
+      None
+    }
+  }
+
+  /// Element type of an array
+  pub fn element(&self) -> Option<Self> {
+    let ty = self.strip();
+    if ty.is_composite() && ty.tag == DebugTag::ArrayType {
+      ty.base_type()
+    } else {
+      None
+    }
+  }
+
+  /// Name & type of the field of a struct or class at the offset, which comes
+  /// from the data layout of the LLVM struct type. Base classes have empty
+  /// names. `None` when the field is a bitfield, whose storage is shared, or
+  /// when no member or several members, e.g. of a union, are at the offset
+  pub fn field_at_offset(&self, offset_in_bits: u64) -> Option<(String, Self)> {
+    let ty = self.strip();
+    if !ty.is_composite() || ty.tag == DebugTag::UnionType {
+      return None;
+    }
+    let elements = md_operand(ty.md, 4)?;
+    let num_elements = unsafe { LLVMGetMDNodeNumOperands(elements) } as usize;
+    let mut members = (0..num_elements)
+      .filter_map(|i| md_operand(elements, i).map(Self::new))
+      .filter(|element| element.is_derived() && element.flags() & LLVMDIFlagStaticMember == 0)
+      .filter(|element| element.tag == DebugTag::Member || element.tag == DebugTag::Inheritance)
+      .filter(|element| element.offset_in_bits() == offset_in_bits);
+    let member = members.next()?;
+    if members.next().is_some() || member.flags() & LLVMDIFlagBitField != 0 {
+      return None;
+    }
+    Some((member.name().unwrap_or_default(), member.base_type()?))
+  }
+}
+
+/// A source variable described by a `llvm.dbg.*` intrinsic
+#[derive(Debug, Clone)]
+pub struct DebugVariable<'ctx> {
+  pub name: String,
+  pub ty: Option<DebugType<'ctx>>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DebugIntrinsicKind {
+  /// `llvm.dbg.declare` & `llvm.dbg.addr`, where the operand is the address of the variable
+  Address,
+
+  /// `llvm.dbg.value`, where the operand is the value of the variable
+  Value,
+}
+
+pub trait DebugIntrinsicUtil<'ctx> {
+  /// The operand & the source variable of a `llvm.dbg.*` intrinsic call
+  fn debug_variable(&self) -> Option<(DebugIntrinsicKind, Operand<'ctx>, DebugVariable<'ctx>)>;
+}
+
+impl<'ctx> DebugIntrinsicUtil<'ctx> for CallInstruction<'ctx> {
+  fn debug_variable(&self) -> Option<(DebugIntrinsicKind, Operand<'ctx>, DebugVariable<'ctx>)> {
+    let kind = match self.callee_function()?.name().as_str() {
+      "llvm.dbg.declare" | "llvm.dbg.addr" => DebugIntrinsicKind::Address,
+      "llvm.dbg.value" => DebugIntrinsicKind::Value,
+      _ => return None,
+    };
+    unsafe {
+      let operand = md_operand(LLVMGetOperand(self.value_ref(), 0), 0)?;
+      let variable = LLVMGetOperand(self.value_ref(), 1);
+      let name = md_string(md_operand(variable, 1)?)?;
+      let ty = md_operand(variable, 3).map(DebugType::new);
+      Some((kind, Operand::from_llvm(operand), DebugVariable { name, ty }))
+    }
+  }
+}
+
+/// Source location of an instruction, with the call sites it is inlined into
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DebugLocation {
+  pub file: String,
+  pub line: u32,
+  pub col: u32,
+
+  /// Name of the source function enclosing the location
+  #[serde(default)]
+  pub func: Option<String>,
+
+  /// Location of the call the enclosing function is inlined at
+  #[serde(default)]
+  pub inlined_at: Option<Box<DebugLocation>>,
+}
+
+impl DebugLocation {
+  fn from_metadata(ctx: LLVMContextRef, loc: LLVMMetadataRef) -> Option<Self> {
+    if loc.is_null() {
+      return None;
+    }
+    unsafe {
+      let scope = LLVMDILocationGetScope(loc);
+      let file = LLVMDIScopeGetFile(scope);
+      let file = if file.is_null() {
+        String::new()
+      } else {
+        let mut len = 0;
+        let ptr = LLVMDIFileGetFilename(file, &mut len);
+        let bytes = std::slice::from_raw_parts(ptr as *const u8, len as usize);
+        String::from_utf8_lossy(bytes).to_string()
+      };
+      Some(Self {
+        file,
+        line: LLVMDILocationGetLine(loc),
+        col: LLVMDILocationGetColumn(loc),
+        func: subprogram_name(LLVMMetadataAsValue(ctx, scope)),
+        inlined_at: Self::from_metadata(ctx, LLVMDILocationGetInlinedAt(loc)).map(Box::new),
+      })
+    }
+  }
+
+  /// The locations from the instruction out to the outermost inlining call site
+  pub fn inlined_chain(&self) -> Vec<&DebugLocation> {
+    let mut chain = vec![self];
+    while let Some(inlined_at) = &chain[chain.len() - 1].inlined_at {
+      chain.push(inlined_at);
+    }
+    chain
+  }
+}
+
+impl std::fmt::Display for DebugLocation {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(f, "{}:{}:{}", self.file, self.line, self.col)
+  }
+}
+
+/// Name of the subprogram a scope is nested in, going out of lexical blocks
+fn subprogram_name(mut scope: LLVMValueRef) -> Option<String> {
+  loop {
+    let kind = unsafe { LLVMGetMetadataKind(LLVMValueAsMetadata(scope)) };
+    match kind {
+      LLVMMetadataKind::LLVMDISubprogramMetadataKind => return md_string(md_operand(scope, 2)?),
+      LLVMMetadataKind::LLVMDILexicalBlockMetadataKind | LLVMMetadataKind::LLVMDILexicalBlockFileMetadataKind => {
+        scope = md_operand(scope, 1)?
+      }
+      _ => return None,
+    }
+  }
+}
+
+pub trait DebugLocationUtil {
+  /// Structured debug location, `None` when the instruction has no debug info
+  fn debug_location(&self) -> Option<DebugLocation>;
+}
+
+impl<'ctx> DebugLocationUtil for Instruction<'ctx> {
+  fn debug_location(&self) -> Option<DebugLocation> {
+    unsafe {
+      let ctx = LLVMGetTypeContext(LLVMTypeOf(self.value_ref()));
+      DebugLocation::from_metadata(ctx, LLVMInstructionGetDebugLoc(self.value_ref()))
+    }
+  }
+}
+
+impl<'ctx> DebugLocationUtil for CallInstruction<'ctx> {
+  fn debug_location(&self) -> Option<DebugLocation> {
+    self.as_instruction().debug_location()
+  }
+}
@@ -0,0 +1,19 @@
+use std::fs;
+use std::path::Path;
+
+/// Recursively copy the directory `from` into `to`, creating `to` when necessary
+pub fn copy_dir_all(from: &Path, to: &Path) -> Result<(), String> {
+  fs::create_dir_all(to).map_err(|_| format!("Cannot create directory {:?}", to))?;
+  let entries = fs::read_dir(from).map_err(|_| format!("Cannot read directory {:?}", from))?;
+  for entry in entries {
+    let entry = entry.map_err(|_| format!("Cannot read entry in directory {:?}", from))?;
+    let path = entry.path();
+    let dest = to.join(entry.file_name());
+    if path.is_dir() {
+      copy_dir_all(&path, &dest)?;
+    } else {
+      fs::copy(&path, &dest).map_err(|_| format!("Cannot copy file {:?}", path))?;
+    }
+  }
+  Ok(())
+}
@@ -1,8 +1,45 @@
 use llir::{types::*, values::*, *};
+use llvm_sys::core::*;
+use llvm_sys::target::*;
+use llvm_sys::{LLVMAttributeFunctionIndex, LLVMLinkage, LLVMOpcode, LLVMTypeKind};
 use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+
+/// Functions that terminate the program but may be declared without `noreturn`
+const FATAL_FUNCTIONS: [&str; 8] = [
+  "exit",
+  "_exit",
+  "_Exit",
+  "abort",
+  "panic",
+  "__assert_fail",
+  "__stack_chk_fail",
+  "do_exit",
+];
 
 pub trait CallInstrUtil<'ctx> {
   fn is_dummy_intrinsic_call(&self) -> bool;
+
+  /// The name of the intrinsic the call invokes, e.g. `llvm.expect.i64`
+  fn intrinsic_name(&self) -> Option<String>;
+
+  /// Whether the call never returns, i.e. the call or the callee is marked
+  /// `noreturn` or the callee is a known fatal function
+  fn is_noreturn_call(&self) -> bool;
+
+  /// Whether the call is an `invoke`, which can unwind to a landing pad
+  fn is_invoke(&self) -> bool;
+
+  /// The block an `invoke` continues to when the callee returns normally
+  fn normal_destination(&self) -> Option<Block<'ctx>>;
+
+  /// The landing pad an `invoke` continues to when the callee throws
+  fn unwind_destination(&self) -> Option<Block<'ctx>>;
+
+  /// The instruction the result of the call is stored under, as operands
+  /// using it refer to it: the call itself, or the `invoke` as an
+  /// `Instruction::Other`
+  fn result_instruction(&self) -> Instruction<'ctx>;
 }
 
 impl<'ctx> CallInstrUtil<'ctx> for CallInstruction<'ctx> {
@@ -24,16 +61,303 @@ impl<'ctx> CallInstrUtil<'ctx> for CallInstruction<'ctx> {
       false
     }
   }
+
+  fn intrinsic_name(&self) -> Option<String> {
+    if self.is_intrinsic_call() {
+      self.callee_function().map(|function| function.name())
+    } else {
+      None
+    }
+  }
+
+  fn is_noreturn_call(&self) -> bool {
+    let attribute = "noreturn";
+    let kind = unsafe { LLVMGetEnumAttributeKindForName(attribute.as_ptr() as *const _, attribute.len()) };
+    let call_is_noreturn =
+      unsafe { !LLVMGetCallSiteEnumAttribute(self.value_ref(), LLVMAttributeFunctionIndex, kind).is_null() };
+    let callee_is_noreturn = match self.callee_function() {
+      Some(function) => {
+        let has_attribute =
+          unsafe { !LLVMGetEnumAttributeAtIndex(function.value_ref(), LLVMAttributeFunctionIndex, kind).is_null() };
+        has_attribute || FATAL_FUNCTIONS.contains(&function.simp_name().as_str())
+      }
+      None => false,
+    };
+    call_is_noreturn || callee_is_noreturn
+  }
+
+  fn is_invoke(&self) -> bool {
+    unsafe { LLVMGetInstructionOpcode(self.value_ref()) == LLVMOpcode::LLVMInvoke }
+  }
+
+  fn normal_destination(&self) -> Option<Block<'ctx>> {
+    if self.is_invoke() {
+      Some(Block::from_llvm(unsafe { LLVMGetNormalDest(self.value_ref()) }))
+    } else {
+      None
+    }
+  }
+
+  fn unwind_destination(&self) -> Option<Block<'ctx>> {
+    if self.is_invoke() {
+      Some(Block::from_llvm(unsafe { LLVMGetUnwindDest(self.value_ref()) }))
+    } else {
+      None
+    }
+  }
+
+  fn result_instruction(&self) -> Instruction<'ctx> {
+    if self.is_invoke() {
+      Instruction::from_llvm(self.value_ref())
+    } else {
+      self.as_instruction()
+    }
+  }
+}
+
+pub trait ExtractValueInstrUtil<'ctx> {
+  /// The aggregate the value is extracted from
+  fn aggregate_operand(&self) -> Operand<'ctx>;
+
+  fn extract_indices(&self) -> Vec<u32>;
+}
+
+impl<'ctx> ExtractValueInstrUtil<'ctx> for ExtractValueInstruction<'ctx> {
+  fn aggregate_operand(&self) -> Operand<'ctx> {
+    Operand::from_llvm(unsafe { LLVMGetOperand(self.value_ref(), 0) })
+  }
+
+  fn extract_indices(&self) -> Vec<u32> {
+    unsafe {
+      let num_indices = LLVMGetNumIndices(self.value_ref()) as usize;
+      std::slice::from_raw_parts(LLVMGetIndices(self.value_ref()), num_indices).to_vec()
+    }
+  }
+}
+
+pub trait GEPInstrUtil<'ctx> {
+  /// Offsets in bits, under the data layout of the module, of the struct
+  /// fields the indices after the first go into. Array indices have no offset
+  fn field_offsets(&self) -> Vec<Option<u64>>;
+}
+
+impl<'ctx> GEPInstrUtil<'ctx> for GetElementPtrInstruction<'ctx> {
+  fn field_offsets(&self) -> Vec<Option<u64>> {
+    unsafe {
+      let gep = self.value_ref();
+      let module = LLVMGetGlobalParent(LLVMGetBasicBlockParent(LLVMGetInstructionParent(gep)));
+      let data_layout = LLVMGetModuleDataLayout(module);
+      let mut ty = LLVMGetElementType(LLVMTypeOf(LLVMGetOperand(gep, 0)));
+      (2..LLVMGetNumOperands(gep) as u32)
+        .map(|i| match LLVMGetTypeKind(ty) {
+          LLVMTypeKind::LLVMStructTypeKind => {
+            // Struct indices are always constant
+            let field = LLVMConstIntGetZExtValue(LLVMGetOperand(gep, i)) as u32;
+            let offset = LLVMOffsetOfElement(data_layout, ty, field) * 8;
+            ty = LLVMStructGetTypeAtIndex(ty, field);
+            Some(offset)
+          }
+          _ => {
+            ty = LLVMGetElementType(ty);
+            None
+          }
+        })
+        .collect()
+    }
+  }
+}
+
+pub trait InstructionUtil<'ctx> {
+  fn operands(&self) -> Vec<Operand<'ctx>>;
+
+  /// The instruction as a call if it is a `call` or an `invoke`. Invokes are
+  /// not recognized by llir and only show up as `Instruction::Other`
+  fn as_call_like(&self) -> Option<CallInstruction<'ctx>>;
+
+  /// Whether the instruction is a `va_arg`, which llir only sees as `Instruction::Other`
+  fn is_va_arg(&self) -> bool;
+
+  /// The pointer to the `va_list` a `va_arg` reads from
+  fn va_arg_list(&self) -> Option<Operand<'ctx>>;
+}
+
+impl<'ctx> InstructionUtil<'ctx> for Instruction<'ctx> {
+  /// The value operands of the instruction. Block operands of terminators are
+  /// not included
+  fn operands(&self) -> Vec<Operand<'ctx>> {
+    match self {
+      Instruction::Return(ret) => ret.op().into_iter().collect(),
+      Instruction::Branch(BranchInstruction::Conditional(br)) => vec![br.condition()],
+      Instruction::Switch(swi) => vec![swi.condition()],
+      Instruction::Call(call) => call.arguments(),
+      Instruction::Store(st) => vec![st.location(), st.value()],
+      Instruction::Load(ld) => vec![ld.location()],
+      Instruction::ICmp(icmp) => vec![icmp.op0(), icmp.op1()],
+      Instruction::Binary(bin) => vec![bin.op0(), bin.op1()],
+      Instruction::Unary(una) => vec![una.op0()],
+      Instruction::GetElementPtr(gep) => vec![vec![gep.location()], gep.indices()].concat(),
+      Instruction::Phi(phi) => phi.incomings().iter().map(|incoming| incoming.value).collect(),
+      _ => match self.as_call_like() {
+        Some(invoke) => invoke.arguments(),
+        None => vec![],
+      },
+    }
+  }
+
+  fn as_call_like(&self) -> Option<CallInstruction<'ctx>> {
+    match self {
+      Instruction::Call(call) => Some(*call),
+      Instruction::Other(_) if unsafe { !LLVMIsAInvokeInst(self.value_ref()).is_null() } => {
+        Some(CallInstruction::from_llvm(self.value_ref()))
+      }
+      _ => None,
+    }
+  }
+
+  fn is_va_arg(&self) -> bool {
+    match self {
+      Instruction::Other(_) => unsafe { LLVMGetInstructionOpcode(self.value_ref()) == LLVMOpcode::LLVMVAArg },
+      _ => false,
+    }
+  }
+
+  fn va_arg_list(&self) -> Option<Operand<'ctx>> {
+    if self.is_va_arg() {
+      Some(Operand::from_llvm(unsafe { LLVMGetOperand(self.value_ref(), 0) }))
+    } else {
+      None
+    }
+  }
+}
+
+pub trait GlobalUtil<'ctx> {
+  /// The constant data of the global reached by the indices of a GEP into it,
+  /// where the first index steps over the pointer to the global and has to be
+  /// 0. Only constant globals are read, since mutable globals can be changed
+  /// anywhere else
+  fn constant_data(&self, indices: &[i64]) -> Option<Constant<'ctx>>;
+}
+
+impl<'ctx> GlobalUtil<'ctx> for Global<'ctx> {
+  fn constant_data(&self, indices: &[i64]) -> Option<Constant<'ctx>> {
+    if !self.is_constant() {
+      return None;
+    }
+    let mut data = self.initializer()?;
+    if let Some((first, rest)) = indices.split_first() {
+      if *first != 0 {
+        return None;
+      }
+      for index in rest {
+        let elements = match data {
+          Constant::Struct(s) => s.elements(),
+          Constant::Array(a) => a.elements(),
+          _ => return None,
+        };
+        data = elements.get(usize::try_from(*index).ok()?).cloned()?;
+      }
+    }
+    Some(data)
+  }
+}
+
+pub trait BlockUtil<'ctx> {
+  /// Successors of the block, including the normal and unwind destinations
+  /// of a terminating `invoke`
+  fn successor_blocks(&self) -> Vec<Block<'ctx>>;
+}
+
+impl<'ctx> BlockUtil<'ctx> for Block<'ctx> {
+  fn successor_blocks(&self) -> Vec<Block<'ctx>> {
+    let mut blocks = self.destination_blocks().into_iter().collect::<Vec<_>>();
+    if let Some(invoke) = self.last_instruction().and_then(|instr| instr.as_call_like()) {
+      for block in invoke
+        .normal_destination()
+        .into_iter()
+        .chain(invoke.unwind_destination())
+      {
+        if !blocks.contains(&block) {
+          blocks.push(block);
+        }
+      }
+    }
+    blocks
+  }
+}
+
+/// Instructions of the function whose values are derived from the seeds,
+/// including the seeds themselves. Storing a derived value into a location
+/// also makes the location derived
+pub fn forward_data_flow<'ctx>(func: Function<'ctx>, seeds: HashSet<Instruction<'ctx>>) -> HashSet<Instruction<'ctx>> {
+  let mut flows = seeds;
+  let mut changed = true;
+  while changed {
+    changed = false;
+    for instr in func.iter_instructions() {
+      let uses_flow = instr.operands().iter().any(|op| match op {
+        Operand::Instruction(op) => flows.contains(op),
+        _ => false,
+      });
+      if uses_flow && flows.insert(instr) {
+        changed = true;
+        if let Instruction::Store(st) = instr {
+          if let Operand::Instruction(loc) = st.location() {
+            flows.insert(loc);
+          }
+        }
+      }
+    }
+  }
+  flows
+}
+
+pub trait TypeUtil {
+  /// C-like description of the type, e.g. `struct device *` or `i32`. The
+  /// numeric suffixes of struct names, e.g. `struct.device.123` after linking,
+  /// are dropped
+  fn description(&self) -> String;
+}
+
+impl<'ctx> TypeUtil for Type<'ctx> {
+  fn description(&self) -> String {
+    match self {
+      Type::Void(_) => "void".to_string(),
+      Type::Int(i) => format!("i{}", i.width()),
+      Type::Float(_) => "float".to_string(),
+      Type::Pointer(p) => format!("{} *", p.element_type().description()),
+      Type::Array(a) => format!("[{} x {}]", a.num_elements(), a.element_type().description()),
+      Type::Vector(v) => format!("<{}>", v.element_type().description()),
+      Type::Struct(StructType::NamedStruct(ns)) => {
+        let name = ns.name();
+        let name = match name.rfind('.') {
+          Some(i) if name[..i].contains('.') && name[i + 1..].parse::<usize>().is_ok() => &name[..i],
+          _ => &name[..],
+        };
+        name.replacen('.', " ", 1)
+      }
+      Type::Struct(StructType::LiteralStruct(_)) => "struct".to_string(),
+      Type::Function(_) => "function".to_string(),
+      Type::Other(_) => "other".to_string(),
+    }
+  }
 }
 
 pub trait FunctionTypeUtil<'ctx> {
   fn used_types(&self) -> Vec<Type<'ctx>>;
+
+  /// Whether calls can pass an argument at the index, which variadic
+  /// functions allow beyond their declared arguments
+  fn may_have_argument(&self, index: usize) -> bool;
 }
 
 impl<'ctx> FunctionTypeUtil<'ctx> for FunctionType<'ctx> {
   fn used_types(&self) -> Vec<Type<'ctx>> {
     vec![vec![self.return_type()], self.argument_types()].concat()
   }
+
+  fn may_have_argument(&self, index: usize) -> bool {
+    index < self.num_argument_types() || self.is_var_arg()
+  }
 }
 
 pub trait FunctionUtil<'ctx> {
@@ -42,24 +366,51 @@ pub trait FunctionUtil<'ctx> {
   fn used_types(&self) -> Vec<Type<'ctx>>;
 
   fn used_struct_names(&self) -> HashSet<String>;
+
+  /// Struct names used by the signature and by the instructions of the body,
+  /// including the types of the operands, e.g. the globals a GEP goes into
+  fn used_body_struct_names(&self) -> HashSet<String>;
+
+  /// Whether the function is visible outside of its translation unit, i.e. not `static`
+  fn is_externally_visible(&self) -> bool;
+
+  /// Whether the function is used other than being directly called, e.g. stored
+  /// into an ops struct as a callback
+  fn is_address_taken(&self) -> bool;
 }
 
-impl<'ctx> FunctionUtil<'ctx> for Function<'ctx> {
-  fn simp_name(&self) -> String {
-    let name = self.name();
-    match name.find('.') {
-      Some(i) => {
-        if &name[..i] == "llvm" {
-          match name.chars().skip(i + 2).position(|c| c == '.') {
-            Some(j) => name[i + 1..i + 2 + j].to_string(),
-            None => name[i + 1..].to_string(),
-          }
-        } else {
-          name[..i].to_string()
+/// Simplify a function name by removing the `.xxx` suffixes (and the `llvm.`
+/// prefix of intrinsics), then demangling Itanium C++ names without their
+/// parameters, so that e.g. the overloads `_ZN3foo3barEi` and `_ZN3foo3barEv`
+/// are both simplified to `foo::bar`
+pub fn simplify_name(name: &str) -> String {
+  let name = match name.find('.') {
+    Some(i) => {
+      if &name[..i] == "llvm" {
+        match name.chars().skip(i + 2).position(|c| c == '.') {
+          Some(j) => &name[i + 1..i + 2 + j],
+          None => &name[i + 1..],
         }
+      } else {
+        &name[..i]
       }
-      None => name,
     }
+    None => name,
+  };
+  if name.starts_with("_Z") {
+    let options = cpp_demangle::DemangleOptions::new().no_params();
+    match cpp_demangle::Symbol::new(name).map(|symbol| symbol.demangle(&options)) {
+      Ok(Ok(demangled)) => demangled,
+      _ => name.to_string(),
+    }
+  } else {
+    name.to_string()
+  }
+}
+
+impl<'ctx> FunctionUtil<'ctx> for Function<'ctx> {
+  fn simp_name(&self) -> String {
+    simplify_name(self.name().as_str())
   }
 
   fn used_types(&self) -> Vec<Type<'ctx>> {
@@ -68,33 +419,87 @@ impl<'ctx> FunctionUtil<'ctx> for Function<'ctx> {
   }
 
   fn used_struct_names(&self) -> HashSet<String> {
+    struct_names(self.used_types())
+  }
+
+  fn used_body_struct_names(&self) -> HashSet<String> {
     let mut types = self.used_types();
-    let mut struct_names = HashSet::new();
-    while !types.is_empty() {
-      let t = types.pop().unwrap();
-      match t {
-        Type::Function(ft) => {
-          for t in ft.used_types() {
-            types.push(t);
-          }
-        }
-        Type::Struct(StructType::NamedStruct(ns)) => {
-          struct_names.insert(ns.name());
+    for instr in self.iter_instructions() {
+      types.push(instr.get_type());
+      for operand in instr.operands() {
+        match operand {
+          Operand::Constant(constant) => constant_types(constant, &mut types),
+          _ => types.push(operand.get_type()),
         }
-        Type::Array(a) => {
-          types.push(a.element_type());
-        }
-        Type::Pointer(p) => {
-          types.push(p.element_type());
+      }
+    }
+    struct_names(types)
+  }
+
+  fn is_externally_visible(&self) -> bool {
+    match unsafe { LLVMGetLinkage(self.value_ref()) } {
+      LLVMLinkage::LLVMInternalLinkage | LLVMLinkage::LLVMPrivateLinkage => false,
+      _ => true,
+    }
+  }
+
+  fn is_address_taken(&self) -> bool {
+    let func_ref = self.value_ref();
+    unsafe {
+      let mut use_ref = LLVMGetFirstUse(func_ref);
+      while !use_ref.is_null() {
+        let user = LLVMGetUser(use_ref);
+        let is_call = !LLVMIsACallInst(user).is_null() || !LLVMIsAInvokeInst(user).is_null();
+        if !is_call || LLVMGetCalledValue(user) != func_ref {
+          return true;
         }
-        Type::Vector(v) => {
-          types.push(v.element_type());
+        use_ref = LLVMGetNextUse(use_ref);
+      }
+    }
+    false
+  }
+}
+
+/// Types of the constant and of the constants it is computed from
+fn constant_types<'ctx>(constant: Constant<'ctx>, types: &mut Vec<Type<'ctx>>) {
+  types.push(constant.get_type());
+  match constant {
+    Constant::ConstExpr(ConstExpr::GetElementPtr(gep)) => constant_types(gep.location(), types),
+    Constant::ConstExpr(ConstExpr::Unary(una)) => constant_types(una.op0(), types),
+    Constant::ConstExpr(ConstExpr::Binary(bin)) => {
+      constant_types(bin.op0(), types);
+      constant_types(bin.op1(), types);
+    }
+    _ => {}
+  }
+}
+
+fn struct_names<'ctx>(mut types: Vec<Type<'ctx>>) -> HashSet<String> {
+  let mut struct_names = HashSet::new();
+  while !types.is_empty() {
+    let t = types.pop().unwrap();
+    match t {
+      Type::Function(ft) => {
+        for t in ft.used_types() {
+          types.push(t);
         }
-        _ => {}
       }
+      Type::Struct(StructType::NamedStruct(ns)) => {
+        struct_names.insert(ns.name());
+      }
+      Type::Array(a) => {
+        types.push(a.element_type());
+      }
+      Type::Pointer(p) => {
+        types.push(p.element_type());
+      }
+      Type::Vector(v) => {
+        types.push(v.element_type());
+      }
+      _ => {}
     }
-    struct_names
   }
+  struct_names
 }
 
 pub trait BlockTraceComparison {
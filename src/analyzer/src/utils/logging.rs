@@ -52,6 +52,20 @@ impl LoggingContext {
     self.log(format!("{} slices generated, dumping slices to json...", num_slices).as_str())
   }
 
+  pub fn log_loading_prev_bc(&mut self) -> Result<(), String> {
+    self.log("Loading byte code file of the previous version...")
+  }
+
+  pub fn log_reused_slices(&mut self, num_changed_funcs: usize, num_reused_slices: usize) -> Result<(), String> {
+    self.log(
+      format!(
+        "{} functions changed, reusing {} slices from the previous version",
+        num_changed_funcs, num_reused_slices
+      )
+      .as_str(),
+    )
+  }
+
   pub fn log_dividing_batches(&mut self, use_batch: bool) -> Result<(), String> {
     if use_batch {
       self.log("Slices dumped, dividing slices into batches")
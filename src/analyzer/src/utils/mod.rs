@@ -1,10 +1,16 @@
 mod batching;
 mod cartesian;
+mod debug_info;
+mod files;
 mod json;
 mod llvm;
 mod logging;
+mod sampling;
 pub use batching::*;
 pub use cartesian::*;
+pub use debug_info::*;
+pub use files::*;
 pub use json::*;
 pub use llvm::*;
 pub use logging::*;
+pub use sampling::*;
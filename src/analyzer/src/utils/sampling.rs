@@ -0,0 +1,94 @@
+use rand::{seq::index, Rng};
+use std::collections::BTreeMap;
+
+/// Allocate `budget` samples across strata of the given sizes. Every non-empty
+/// stratum gets one sample when the budget allows, and the rest of the budget
+/// is allocated proportionally to the stratum sizes (largest remainder first)
+pub fn stratified_allocation(sizes: &[usize], budget: usize) -> Vec<usize> {
+  let total: usize = sizes.iter().sum();
+  if total <= budget {
+    return sizes.to_vec();
+  }
+
+  // Not enough budget to cover every stratum: prefer the largest ones
+  let num_non_empty = sizes.iter().filter(|size| **size > 0).count();
+  let mut indices = (0..sizes.len()).collect::<Vec<_>>();
+  if budget <= num_non_empty {
+    indices.sort_by(|i, j| sizes[*j].cmp(&sizes[*i]).then(i.cmp(j)));
+    let mut allocation = vec![0; sizes.len()];
+    for i in indices.into_iter().take(budget) {
+      allocation[i] = 1;
+    }
+    return allocation;
+  }
+
+  // One sample per stratum, then proportional to the remaining sizes
+  let mut allocation = sizes.iter().map(|size| (*size).min(1)).collect::<Vec<_>>();
+  let remaining_budget = budget - num_non_empty;
+  let remaining_total = total - num_non_empty;
+  let quotas = sizes
+    .iter()
+    .map(|size| (remaining_budget * size.saturating_sub(1)) as f64 / remaining_total as f64)
+    .collect::<Vec<_>>();
+  for (i, quota) in quotas.iter().enumerate() {
+    allocation[i] += quota.floor() as usize;
+  }
+  let mut leftover = budget - allocation.iter().sum::<usize>();
+  let remainder = |i: usize| quotas[i] - quotas[i].floor();
+  indices.sort_by(|i, j| remainder(*j).partial_cmp(&remainder(*i)).unwrap().then(i.cmp(j)));
+  for i in indices {
+    if leftover == 0 {
+      break;
+    }
+    if allocation[i] < sizes[i] {
+      allocation[i] += 1;
+      leftover -= 1;
+    }
+  }
+  allocation
+}
+
+/// Sample at most `budget` items, stratified by the key returned by `stratum_of`.
+/// Each sampled item comes with its sampling weight, i.e. the number of items
+/// in its stratum represented by that sample. When the budget cannot cover
+/// every stratum, the items of the dropped strata are spread over the sampled
+/// ones, so that the weights still sum up to the number of items
+pub fn stratified_sample<T, K, F, R>(items: Vec<T>, stratum_of: F, budget: usize, rng: &mut R) -> Vec<(T, f64)>
+where
+  K: Ord,
+  F: Fn(&T) -> K,
+  R: Rng,
+{
+  let mut strata = BTreeMap::new();
+  for item in items {
+    strata.entry(stratum_of(&item)).or_insert_with(Vec::new).push(item);
+  }
+  let sizes = strata.values().map(|items| items.len()).collect::<Vec<_>>();
+  let allocation = stratified_allocation(&sizes, budget);
+  let total: usize = sizes.iter().sum();
+  let covered: usize = sizes
+    .iter()
+    .zip(&allocation)
+    .filter(|(_, n)| **n > 0)
+    .map(|(size, _)| size)
+    .sum();
+  let scale = if covered == 0 {
+    1.0
+  } else {
+    total as f64 / covered as f64
+  };
+  let mut result = Vec::new();
+  for (items, num_samples) in strata.into_iter().map(|(_, items)| items).zip(allocation) {
+    if num_samples == 0 {
+      continue;
+    }
+    let weight = items.len() as f64 / num_samples as f64 * scale;
+    let mut sampled_indices = index::sample(rng, items.len(), num_samples).into_vec();
+    sampled_indices.sort();
+    let mut items = items.into_iter().map(Some).collect::<Vec<_>>();
+    for i in sampled_indices {
+      result.push((items[i].take().unwrap(), weight));
+    }
+  }
+  result
+}
@@ -55,6 +55,9 @@ where
     callee: target_func,
     instr: call_instr,
     functions: vec![caller_func, caller_func, target_func].iter().cloned().collect(),
+    reduction_report: None,
+    sampling_weight: 1.0,
+    depth: 1,
   };
 
   f(call_graph, slice);
@@ -73,7 +76,7 @@ fn test_slice_function_trace(path: &Path, entry: &str, caller: &str, target: &st
 fn test_block_trace(path: &Path, entry: &str, caller: &str, target: &str, max_traces: usize) -> Result<(), String> {
   process_slice(path, entry, caller, target, |cg, slice| {
     // Get the function traces
-    let block_traces = slice.block_traces(&cg, 1, max_traces);
+    let block_traces = slice.block_traces(&cg, 1, max_traces, 0, false).collect::<Vec<_>>();
     println!("{:?}", block_traces);
   })
 }
@@ -125,3 +128,62 @@ fn slice_block_trace_example_temp() -> Result<(), String> {
 //   let path = Path::new("/home/aspire/programs/linux_kernel/linux-4.5-rc4/vmlinux.bc");
 //   test_block_trace(path, "vbt_panel_init", "vbt_panel_init", "devm_kzalloc", 50)
 // }
+
+#[test]
+fn find_paths_unrolled_loop() -> Result<(), String> {
+  let ctx = Context::create();
+  let module = ctx.load_module(Path::new("tests/c_files/loop/unroll_1.bc"))?;
+  let func = module.get_function("poll_then_alloc").unwrap();
+  let block_graph = func.block_graph();
+  let entry = func.first_block().unwrap();
+  let exit = func
+    .iter_instructions()
+    .find_map(|instr| match instr {
+      Instruction::Return(ret) => Some(ret.parent_block()),
+      _ => None,
+    })
+    .unwrap();
+  let max_visits = |paths: &Vec<Vec<Block>>| {
+    paths
+      .iter()
+      .map(|path| {
+        path
+          .iter()
+          .map(|b| path.iter().filter(|o| *o == b).count())
+          .max()
+          .unwrap()
+      })
+      .max()
+      .unwrap()
+  };
+  let simple_paths = block_graph.find_paths(entry, exit, 50, 0);
+  let unrolled_paths = block_graph.find_paths(entry, exit, 50, 1);
+  assert_eq!(max_visits(&simple_paths), 1);
+  assert_eq!(max_visits(&unrolled_paths), 2, "Loop should be unrolled once");
+  assert!(unrolled_paths.len() > simple_paths.len());
+  Ok(())
+}
+
+#[test]
+fn block_traces_to_returning_exits() -> Result<(), String> {
+  let ctx = Context::create();
+  let module = ctx.load_module(Path::new("tests/c_files/trace/post_target.bc"))?;
+  let func = module.get_function("alloc_or_die").unwrap();
+  let call = func
+    .iter_instructions()
+    .find(|instr| match instr {
+      Instruction::Call(call) => call.callee_function().map(|f| f.simp_name()) == Some("kzalloc".to_string()),
+      _ => false,
+    })
+    .unwrap();
+  let mut cache = BlockGraphCache::new();
+  let block_traces = func.block_traces_from_instr_to_exits(call, 50, 0, &mut cache);
+  assert!(!block_traces.is_empty());
+  for block_trace in block_traces {
+    match block_trace.last().unwrap().last_instruction() {
+      Some(Instruction::Return(_)) => {}
+      _ => panic!("Block traces should not end at the unreachable block after abort"),
+    }
+  }
+  Ok(())
+}
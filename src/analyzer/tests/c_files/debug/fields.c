@@ -0,0 +1,26 @@
+struct flags {
+  unsigned ready : 1;
+  unsigned busy : 1;
+  int count;
+};
+
+union data {
+  int i;
+  char *s;
+};
+
+struct device {
+  struct flags flags;
+  union data data;
+  struct device *next;
+};
+
+typedef struct device *device_t;
+
+int use_device(struct device *dev, device_t alias) {
+  struct device local;
+  local.flags.count = dev->flags.count;
+  local.data.i = 1;
+  local.next = dev->next;
+  return local.flags.count + alias->data.i;
+}
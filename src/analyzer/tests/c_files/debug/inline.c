@@ -0,0 +1,9 @@
+void *kzalloc(int size);
+
+static inline __attribute__((always_inline)) void *alloc_buf(int size) {
+  return kzalloc(size);
+}
+
+void *probe() {
+  return alloc_buf(30);
+}
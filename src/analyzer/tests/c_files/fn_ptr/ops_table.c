@@ -0,0 +1,34 @@
+struct dev_ops {
+  int (*open)(int);
+  int (*close)(int);
+};
+
+void *kzalloc(int size);
+void log_open(int flags);
+
+static int open_dev(int flags) {
+  log_open(flags);
+  return flags;
+}
+
+static int close_dev(int flags) {
+  return 0;
+}
+
+static const struct dev_ops ops = {
+  .open = open_dev,
+  .close = close_dev,
+};
+
+void *run(int flags) {
+  if (ops.open(flags)) {
+    return 0;
+  }
+  return kzalloc(30);
+}
+
+void *entry(int flags) {
+  void *ptr = run(flags);
+  open_dev(0);
+  return ptr;
+}
@@ -0,0 +1,8 @@
+struct object {
+  int id;
+  int size;
+};
+
+int get_size(struct object *obj) {
+  return obj->size;
+}
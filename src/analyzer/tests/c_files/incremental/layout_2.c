@@ -0,0 +1,8 @@
+struct object {
+  long id;
+  int size;
+};
+
+int get_size(struct object *obj) {
+  return obj->size;
+}
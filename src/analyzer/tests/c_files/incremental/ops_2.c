@@ -0,0 +1,19 @@
+struct ops {
+  int (*open)(int);
+  int (*close)(int);
+};
+
+int open_a(int fd) { return fd; }
+
+int open_b(int fd) { return -fd; }
+
+int close_a(int fd) { return 0; }
+
+static const struct ops dev_ops = {
+  .open = open_b,
+  .close = close_a,
+};
+
+int run(int fd) {
+  return dev_ops.open(fd);
+}
@@ -0,0 +1,45 @@
+#include <string.h>
+
+#define unlikely(x) __builtin_expect(!!(x), 0)
+
+void *kzalloc(int size);
+
+struct inner {
+  int size;
+};
+
+struct outer {
+  struct inner in;
+  int pad;
+};
+
+void *alloc_unlikely_null(int size) {
+  void *p = kzalloc(size);
+  if (unlikely(p == 0)) {
+    return 0;
+  }
+  return p;
+}
+
+void *alloc_array(int n, int size) {
+  int total;
+  if (__builtin_mul_overflow(n, size, &total)) {
+    return 0;
+  }
+  return kzalloc(total);
+}
+
+void *alloc_copied_size(struct outer *src) {
+  struct outer dst;
+  if (src->in.size > 100) {
+    return 0;
+  }
+  memcpy(&dst, src, sizeof(dst));
+  return kzalloc(dst.in.size);
+}
+
+void *alloc_cleared_size(struct outer *o) {
+  o->in.size = 10;
+  memset(o, 0, sizeof(*o));
+  return kzalloc(o->in.size);
+}
@@ -0,0 +1,9 @@
+void *kzalloc(int size);
+void poll(void);
+
+void *poll_then_alloc(int busy) {
+  while (busy) {
+    poll();
+  }
+  return kzalloc(30);
+}
@@ -0,0 +1,35 @@
+int target(int x);
+void fill(char *buf);
+
+int result_escapes(int x) {
+  return target(x);
+}
+
+int result_escapes_twice(int x) {
+  return result_escapes(x);
+}
+
+void uses_result(int x) {
+  int r = result_escapes_twice(x);
+  if (r) {
+    fill(0);
+  }
+}
+
+int int_argument_returned(int x) {
+  target(x);
+  return x;
+}
+
+void calls_int_argument_returned() {
+  int_argument_returned(1);
+}
+
+void pointer_argument(char *buf) {
+  fill(buf);
+}
+
+void calls_pointer_argument() {
+  char buf[4];
+  pointer_argument(buf);
+}
@@ -0,0 +1,33 @@
+struct ops {
+  int (*handler)(int);
+};
+
+int target(int x);
+
+static int helper(int x) {
+  return target(x);
+}
+
+static int middle(int x) {
+  return helper(x);
+}
+
+int api_call(int x) {
+  return middle(x);
+}
+
+static int callback(int x) {
+  return middle(x) + 1;
+}
+
+struct ops dev_ops = {
+  .handler = callback,
+};
+
+static int dev_probe(int x) {
+  return api_call(x);
+}
+
+int dispatch(int x) {
+  return dev_probe(x);
+}
@@ -0,0 +1,29 @@
+struct device {
+  int id;
+};
+
+struct buffer {
+  int len;
+};
+
+extern struct device the_dev;
+
+void *dev_alloc(struct device *dev, int size);
+void dev_free(void *ptr);
+int dev_register(struct device *dev);
+int buf_len(struct buffer *buf);
+void log_msg(const char *msg);
+
+int touch_global(void) {
+  return the_dev.id;
+}
+
+int probe(struct device *dev, struct buffer *buf) {
+  void *ptr = dev_alloc(dev, buf_len(buf));
+  log_msg("probing");
+  if (!ptr) {
+    return touch_global();
+  }
+  dev_free(ptr);
+  return dev_register(dev);
+}
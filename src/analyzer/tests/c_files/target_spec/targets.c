@@ -0,0 +1,32 @@
+struct device {
+  int id;
+};
+
+struct device *dev_alloc(int size);
+
+int dev_register(struct device *dev, int flags);
+
+void *buf_alloc(unsigned long size);
+
+struct device *probe(int size) {
+  struct device *dev = dev_alloc(size);
+  if (!dev) {
+    return 0;
+  }
+  dev_register(dev, 0);
+  buf_alloc(size);
+  return dev;
+}
+
+int init(int size) {
+  return probe(size) != 0;
+}
+
+int bind() {
+  // The block scope struct is named `struct.device.0`, as linked structs are
+  struct device {
+    long addr;
+  };
+  extern int dev_bind(struct device *dev);
+  return dev_bind(0);
+}
@@ -0,0 +1,38 @@
+#include <stdlib.h>
+
+void *kzalloc(int size);
+void report_bug(const char *file, int line);
+
+#define BUG()                       \
+  do {                              \
+    report_bug(__FILE__, __LINE__); \
+    __builtin_unreachable();        \
+  } while (0)
+
+__attribute__((noreturn)) static void die(int code) {
+  exit(code);
+}
+
+void *alloc_or_exit(void) {
+  void *ptr = kzalloc(30);
+  if (!ptr) {
+    exit(1);
+  }
+  return ptr;
+}
+
+void *alloc_or_bug(void) {
+  void *ptr = kzalloc(30);
+  if (!ptr) {
+    BUG();
+  }
+  return ptr;
+}
+
+void *alloc_or_die(void) {
+  void *ptr = kzalloc(30);
+  if (!ptr) {
+    die(1);
+  }
+  return ptr;
+}
@@ -0,0 +1,18 @@
+void *kzalloc(int size);
+
+void use(void *p);
+
+void *alloc_then_use() {
+  void *p = kzalloc(30);
+  use(p);
+  use(p);
+  use(p);
+  use(p);
+  use(p);
+  use(p);
+  use(p);
+  use(p);
+  use(p);
+  use(p);
+  return p;
+}
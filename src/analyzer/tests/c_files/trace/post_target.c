@@ -0,0 +1,16 @@
+#include <stdlib.h>
+
+void *kzalloc(int size);
+void log_fail(void);
+
+void *alloc_or_die(int fatal) {
+  void *ptr = kzalloc(30);
+  if (!ptr) {
+    if (fatal) {
+      abort();
+    }
+    log_fail();
+    return 0;
+  }
+  return ptr;
+}
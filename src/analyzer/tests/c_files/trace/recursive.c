@@ -0,0 +1,12 @@
+void *kzalloc(int size);
+
+void *alloc_nested(int depth) {
+  if (depth > 0) {
+    return alloc_nested(depth - 1);
+  }
+  return kzalloc(30);
+}
+
+void *probe(int depth) {
+  return alloc_nested(depth);
+}
@@ -0,0 +1,47 @@
+#include <stdarg.h>
+#include <stdlib.h>
+
+void log_err(const char *fmt, ...);
+
+void *alloc_all(int n, ...) {
+  va_list ap;
+  void *last = 0;
+  va_start(ap, n);
+  for (int i = 0; i < n; i++) {
+    int size = va_arg(ap, int);
+    last = malloc(size);
+    if (!last) {
+      log_err("cannot allocate %d bytes", size);
+      break;
+    }
+  }
+  va_end(ap);
+  return last;
+}
+
+void run() {
+  void *ptr = alloc_all(2, 16, 32);
+  if (!ptr) {
+    return;
+  }
+}
+
+void *alloc_from_list(va_list ap) {
+  return malloc(va_arg(ap, int));
+}
+
+void *alloc_copied(int n, ...) {
+  va_list ap, copy;
+  void *ptr;
+  va_start(ap, n);
+  va_arg(ap, int);
+  va_copy(copy, ap);
+  ptr = alloc_from_list(copy);
+  va_end(copy);
+  va_end(ap);
+  return ptr;
+}
+
+void run_copied() {
+  alloc_copied(2, 16, 32);
+}
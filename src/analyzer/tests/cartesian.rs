@@ -19,4 +19,23 @@ fn test_cartesian_3() {
   let result = cartesian(&vec![3]);
   println!("{:?}", result);
   // assert_eq!(result, vec![vec![0]]);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_cartesian_iterator() {
+  let result = CartesianIterator::new(vec![2, 3, 2]).collect::<Vec<_>>();
+  assert_eq!(result, cartesian(&vec![2, 3, 2]));
+  assert_eq!(CartesianIterator::new(vec![2, 0]).count(), 0);
+}
+
+#[test]
+fn test_diagonal_cartesian_iterator() {
+  let sizes = vec![3, 1, 4];
+  let result = DiagonalCartesianIterator::new(sizes.clone()).collect::<Vec<_>>();
+  assert_eq!(result.len(), 12);
+  for (dim, size) in sizes.iter().enumerate() {
+    for i in 0..*size {
+      assert!(result[..4].iter().any(|indices| indices[dim] == i));
+    }
+  }
+}
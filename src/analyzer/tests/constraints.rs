@@ -0,0 +1,25 @@
+use analyzer::semantics::rced::*;
+use analyzer::symbolic_execution::*;
+use llir::values::ICmpPredicate;
+use std::rc::Rc;
+
+#[test]
+fn test_constraints_to_smtlib2() {
+  let cond = Value::ICmp {
+    pred: ICmpPredicate::SLT,
+    op0: Rc::new(Value::Arg(0)),
+    op1: Rc::new(Value::Int(-3)),
+  }
+  .as_comparison()
+  .unwrap();
+  let constraints = Constraints::from(vec![Constraint {
+    cond,
+    branch: false,
+    loc: "main.c:3:7".to_string(),
+    node_id: 2,
+  }]);
+  assert_eq!(
+    constraints.to_smtlib2(),
+    "(declare-const s0 Int)\n; main.c:3:7 at node 2\n(assert (not (< s0 (- 3))))\n(check-sat)\n"
+  );
+}
@@ -0,0 +1,41 @@
+use llir::{values::*, *};
+use std::path::Path;
+
+use analyzer::utils::*;
+
+#[test]
+fn test_inlined_chain() {
+  let loc: DebugLocation = serde_json::from_str(
+    r#"{
+      "file": "list.h", "line": 12, "col": 3, "func": "list_add",
+      "inlined_at": { "file": "dev.c", "line": 40, "col": 5, "func": "dev_probe" }
+    }"#,
+  )
+  .unwrap();
+  let chain = loc.inlined_chain();
+  assert_eq!(chain.len(), 2);
+  assert_eq!(chain[0].func, Some("list_add".to_string()));
+  assert_eq!(chain[1].to_string(), "dev.c:40:5");
+  assert_eq!(chain[1].inlined_at, None);
+}
+
+#[test]
+fn test_inlined_debug_location() -> Result<(), String> {
+  let ctx = Context::create();
+  let module = ctx.load_module(Path::new("tests/c_files/debug/inline.bc"))?;
+  let func = module.get_function("probe").unwrap();
+  let call = func
+    .iter_instructions()
+    .find_map(|instr| match instr {
+      Instruction::Call(call) if call.callee_function().map(|f| f.name()) == Some("kzalloc".to_string()) => Some(call),
+      _ => None,
+    })
+    .unwrap();
+  let loc = call.debug_location().unwrap();
+  assert!(loc.file.ends_with("inline.c"));
+  assert_eq!((loc.line, loc.func.clone()), (4, Some("alloc_buf".to_string())));
+  let chain = loc.inlined_chain();
+  assert_eq!(chain.len(), 2, "The call is inlined once");
+  assert_eq!((chain[1].line, chain[1].func.clone()), (8, Some("probe".to_string())));
+  Ok(())
+}
@@ -0,0 +1,63 @@
+use llir::{values::*, *};
+use std::path::Path;
+
+use analyzer::utils::*;
+
+/// The type of the variable declared in the function by `llvm.dbg.declare`
+fn variable_type<'ctx>(func: Function<'ctx>, name: &str) -> DebugType<'ctx> {
+  func
+    .iter_instructions()
+    .find_map(|instr| match instr {
+      Instruction::Call(call) => match call.debug_variable() {
+        Some((_, _, var)) if var.name == name => var.ty,
+        _ => None,
+      },
+      _ => None,
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_debug_type_pointers() -> Result<(), String> {
+  let ctx = Context::create();
+  let module = ctx.load_module(Path::new("tests/c_files/debug/fields.bc"))?;
+  let func = module.get_function("use_device").unwrap();
+  assert!(variable_type(func, "dev").is_pointer());
+  assert!(
+    variable_type(func, "alias").is_pointer(),
+    "Typedefs of pointers are pointers"
+  );
+  assert!(!variable_type(func, "local").is_pointer());
+  assert_eq!(
+    variable_type(func, "alias").pointee().and_then(|ty| ty.name()),
+    Some("device".to_string())
+  );
+  Ok(())
+}
+
+#[test]
+fn test_debug_type_fields_at_offsets() -> Result<(), String> {
+  let ctx = Context::create();
+  let module = ctx.load_module(Path::new("tests/c_files/debug/fields.bc"))?;
+  let func = module.get_function("use_device").unwrap();
+  let device = variable_type(func, "local");
+  let (name, flags) = device.field_at_offset(0).unwrap();
+  assert_eq!(name, "flags");
+  let (name, data) = device.field_at_offset(64).unwrap();
+  assert_eq!(name, "data");
+  let (name, next) = device.field_at_offset(128).unwrap();
+  assert_eq!(name, "next");
+  assert!(next.is_pointer());
+  assert!(device.field_at_offset(32).is_none(), "No member of device starts at 32");
+
+  // The bitfields share their storage, so the storage is not named after one of them
+  assert!(flags.field_at_offset(0).is_none());
+  assert_eq!(
+    flags.field_at_offset(32).map(|(name, _)| name),
+    Some("count".to_string())
+  );
+
+  // All the members of a union are at the same offset
+  assert!(data.field_at_offset(0).is_none());
+  Ok(())
+}
@@ -0,0 +1,595 @@
+use llir::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use analyzer::call_graph::*;
+use analyzer::options::*;
+use analyzer::slicer::*;
+use analyzer::symbolic_execution::*;
+use analyzer::target_spec::*;
+use analyzer::utils::*;
+
+struct TempOptions {
+  output_path: PathBuf,
+  slice_depth: usize,
+  loop_unroll_bound: usize,
+  max_recursion_depth: usize,
+  post_target_guidance: bool,
+  max_node_per_trace: usize,
+  keep_truncated_traces: bool,
+  entry_policies: Vec<EntryPolicy>,
+  relatedness_policies: Vec<RelatednessPolicy>,
+}
+
+impl TempOptions {
+  /// Options dumping the traces into a temporary directory named after the test
+  fn new(test_name: &str) -> Self {
+    let output_path = std::env::temp_dir().join("analyzer-tests").join(test_name);
+    fs::remove_dir_all(&output_path).ok();
+    Self {
+      output_path,
+      slice_depth: 1,
+      loop_unroll_bound: 0,
+      max_recursion_depth: 0,
+      post_target_guidance: false,
+      max_node_per_trace: 1000,
+      keep_truncated_traces: false,
+      entry_policies: vec![EntryPolicy::Any],
+      relatedness_policies: vec![],
+    }
+  }
+}
+
+impl GeneralOptions for TempOptions {
+  fn use_serial(&self) -> bool {
+    true
+  }
+
+  fn seed(&self) -> u64 {
+    12345
+  }
+}
+
+impl IOOptions for TempOptions {
+  fn input_path(&self) -> PathBuf {
+    PathBuf::new()
+  }
+
+  fn output_path(&self) -> PathBuf {
+    self.output_path.clone()
+  }
+
+  fn default_package(&self) -> Option<&str> {
+    None
+  }
+}
+
+impl CallGraphOptions for TempOptions {
+  fn remove_llvm_funcs(&self) -> bool {
+    true
+  }
+}
+
+impl SlicerOptions for TempOptions {
+  fn no_reduce_slice(&self) -> bool {
+    true
+  }
+
+  fn slice_depth(&self) -> usize {
+    self.slice_depth
+  }
+
+  fn adaptive_slice_depth(&self) -> bool {
+    false
+  }
+
+  fn max_slice_depth(&self) -> usize {
+    self.slice_depth
+  }
+
+  fn entry_filter(&self) -> &Option<String> {
+    &None
+  }
+
+  fn entry_policies(&self) -> &Vec<EntryPolicy> {
+    &self.entry_policies
+  }
+
+  fn entry_patterns(&self) -> &Option<String> {
+    &None
+  }
+
+  fn stop_at_entries(&self) -> bool {
+    false
+  }
+
+  fn max_entry_climb(&self) -> usize {
+    2
+  }
+
+  fn max_entries_per_edge(&self) -> Option<usize> {
+    None
+  }
+
+  fn target_inclusion_filter(&self) -> &Option<String> {
+    &None
+  }
+
+  fn target_exclusion_filter(&self) -> &Option<String> {
+    &None
+  }
+
+  fn target_spec(&self) -> &Option<TargetSpec> {
+    &None
+  }
+
+  fn use_regex_filter(&self) -> bool {
+    false
+  }
+
+  fn max_avg_num_blocks(&self) -> usize {
+    1000
+  }
+
+  fn max_edges_per_target(&self) -> Option<usize> {
+    None
+  }
+
+  fn sampling_stratum(&self) -> SamplingStratum {
+    SamplingStratum::File
+  }
+
+  fn relatedness_policies(&self) -> &Vec<RelatednessPolicy> {
+    &self.relatedness_policies
+  }
+
+  fn relatedness_prefix_length(&self) -> usize {
+    4
+  }
+
+  fn dump_reduction_report(&self) -> bool {
+    false
+  }
+}
+
+impl SymbolicExecutionOptions for TempOptions {
+  fn slice_depth(&self) -> usize {
+    self.slice_depth
+  }
+
+  fn max_work(&self) -> usize {
+    50
+  }
+
+  fn loop_unroll_bound(&self) -> usize {
+    self.loop_unroll_bound
+  }
+
+  fn max_recursion_depth(&self) -> usize {
+    self.max_recursion_depth
+  }
+
+  fn no_random_work(&self) -> bool {
+    true
+  }
+
+  fn max_node_per_trace(&self) -> usize {
+    self.max_node_per_trace
+  }
+
+  fn max_explored_trace_per_slice(&self) -> usize {
+    50
+  }
+
+  fn max_trace_per_slice(&self) -> usize {
+    50
+  }
+
+  fn no_trace_reduction(&self) -> bool {
+    false
+  }
+
+  fn no_prefilter_block_trace(&self) -> bool {
+    false
+  }
+
+  fn keep_truncated_traces(&self) -> bool {
+    self.keep_truncated_traces
+  }
+
+  fn post_target_guidance(&self) -> bool {
+    self.post_target_guidance
+  }
+
+  fn no_prune_infeasible_branch(&self) -> bool {
+    false
+  }
+
+  fn solver(&self) -> SolverKind {
+    if cfg!(feature = "z3") {
+      SolverKind::Z3
+    } else {
+      SolverKind::AlwaysSat
+    }
+  }
+
+  fn solver_command(&self) -> &str {
+    "z3 -in"
+  }
+
+  fn solver_timeout(&self) -> u64 {
+    10000
+  }
+
+  fn print_block_trace(&self) -> bool {
+    false
+  }
+
+  fn print_trace(&self) -> bool {
+    false
+  }
+
+  fn dump_smt(&self) -> bool {
+    false
+  }
+
+  fn target_spec(&self) -> &Option<TargetSpec> {
+    &None
+  }
+}
+
+/// Execute the first slice of the call edge from the caller to the target, and
+/// load the dumped traces in order
+fn execute(
+  path: &Path,
+  caller: &str,
+  target: &str,
+  options: &TempOptions,
+) -> Result<(MetaData, Vec<serde_json::Value>), String> {
+  let ctx = Context::create();
+  let module = ctx.load_module(path)?;
+  let call_graph = CallGraph::from_module(&module, options);
+  let caller_id = call_graph.function_id_map[&module.get_function(caller).unwrap()];
+  let target_id = call_graph.function_id_map[&module.get_function(target).unwrap()];
+  let edge_id = call_graph.graph.find_edge(caller_id, target_id).unwrap();
+  let slice = call_graph
+    .slices_of_call_edge(edge_id, &EntryMatcher::new(options)?, options)
+    .into_iter()
+    .next()
+    .unwrap();
+  let sym_exec_ctx = SymbolicExecutionContext::new(&module, &call_graph, options);
+  let metadata = sym_exec_ctx.execute_target_slices(&target.to_string(), 0, vec![slice]);
+  let mut trace_paths = fs::read_dir(options.trace_target_slice_dir(target, 0))
+    .map_err(|_| "Cannot read trace folder".to_string())?
+    .map(|entry| entry.unwrap().path())
+    .collect::<Vec<_>>();
+  trace_paths.sort_by_key(|path| path.file_stem().unwrap().to_string_lossy().parse::<usize>().unwrap());
+  let traces = trace_paths.iter().map(load_json).collect::<Result<Vec<_>, _>>()?;
+  Ok((metadata, traces))
+}
+
+#[test]
+fn execute_unrolled_loop_with_unchanged_condition() -> Result<(), String> {
+  let path = Path::new("tests/c_files/loop/unroll_1.bc");
+  let mut options = TempOptions::new("execute_unrolled_loop_with_unchanged_condition");
+  options.loop_unroll_bound = 1;
+  let (metadata, traces) = execute(path, "poll_then_alloc", "kzalloc", &options)?;
+  assert_eq!(
+    metadata.path_unsat_trace_count, 0,
+    "Leaving the loop after an iteration should not contradict the loop entry constraint"
+  );
+  assert!(traces.len() >= 2, "Should explore the loop with and without iterations");
+  Ok(())
+}
+
+#[test]
+fn execute_with_post_target_guidance() -> Result<(), String> {
+  let path = Path::new("tests/c_files/trace/post_target.bc");
+  let mut options = TempOptions::new("execute_with_post_target_guidance");
+  options.post_target_guidance = true;
+  let (_, traces) = execute(path, "alloc_or_die", "kzalloc", &options)?;
+  assert!(
+    traces.len() >= 2,
+    "Should explore both the checked and unchecked results"
+  );
+  assert!(
+    traces.iter().all(|trace| trace["exited"] == false),
+    "Post target guidance should lead to the returning exits"
+  );
+  Ok(())
+}
+
+/// The exited traces of the caller in `exits.c`, which are also counted in
+/// the metadata beside the returning ones
+fn exited_traces(caller: &str, options: &TempOptions) -> Result<Vec<serde_json::Value>, String> {
+  let path = Path::new("tests/c_files/trace/exits.bc");
+  let (metadata, traces) = execute(path, caller, "kzalloc", options)?;
+  let (exited, returned): (Vec<_>, Vec<_>) = traces.into_iter().partition(|trace| trace["exited"] == true);
+  assert!(!exited.is_empty(), "The failing allocation should exit");
+  assert!(!returned.is_empty(), "The successful allocation should return");
+  assert_eq!(metadata.exited_trace_count, exited.len());
+  Ok(exited)
+}
+
+/// The function called by the last instruction of the trace
+fn last_callee(trace: &serde_json::Value) -> &serde_json::Value {
+  let instrs = trace["instrs"].as_array().unwrap();
+  &instrs[instrs.len() - 1]["sem"]["Call"]["func"]["Func"]
+}
+
+#[test]
+fn execute_exiting_at_fatal_call() -> Result<(), String> {
+  let options = TempOptions::new("execute_exiting_at_fatal_call");
+  for trace in exited_traces("alloc_or_exit", &options)? {
+    assert_eq!(last_callee(&trace), "exit");
+  }
+  Ok(())
+}
+
+#[test]
+fn execute_exiting_at_unreachable_after_call() -> Result<(), String> {
+  let options = TempOptions::new("execute_exiting_at_unreachable_after_call");
+  for trace in exited_traces("alloc_or_bug", &options)? {
+    assert_eq!(
+      last_callee(&trace),
+      "report_bug",
+      "The call before `unreachable` should end the trace"
+    );
+  }
+  Ok(())
+}
+
+#[test]
+fn execute_exiting_at_noreturn_function_in_slice() -> Result<(), String> {
+  let options = TempOptions::new("execute_exiting_at_noreturn_function_in_slice");
+  for trace in exited_traces("alloc_or_die", &options)? {
+    assert_eq!(last_callee(&trace), "die");
+    assert!(
+      trace["instrs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|instr| !instr["frames"].as_array().unwrap().contains(&serde_json::json!("die"))),
+      "The noreturn function should not be stepped into"
+    );
+  }
+  Ok(())
+}
+
+#[test]
+fn execute_call_through_constant_ops_table() -> Result<(), String> {
+  let path = Path::new("tests/c_files/fn_ptr/ops_table.bc");
+  let options = TempOptions::new("execute_call_through_constant_ops_table");
+  let (_, traces) = execute(path, "run", "kzalloc", &options)?;
+  assert!(!traces.is_empty());
+  for trace in traces {
+    let target = trace["target"].as_u64().unwrap() as usize;
+    let instrs = trace["instrs"].as_array().unwrap();
+    assert!(
+      instrs[..target]
+        .iter()
+        .any(|instr| instr["sem"]["Call"]["func"]["Func"] == "log_open"),
+      "The call through the ops table should step into open_dev"
+    );
+  }
+  Ok(())
+}
+
+#[test]
+fn execute_inlined_target_call() -> Result<(), String> {
+  let path = Path::new("tests/c_files/debug/inline.bc");
+  let options = TempOptions::new("execute_inlined_target_call");
+  let (_, traces) = execute(path, "probe", "kzalloc", &options)?;
+  assert!(!traces.is_empty());
+  for trace in traces {
+    let target = trace["target"].as_u64().unwrap() as usize;
+    let loc = &trace["instrs"][target]["debug_loc"];
+    assert_eq!(loc["func"], "alloc_buf");
+    assert_eq!(loc["inlined_at"]["func"], "probe");
+  }
+  Ok(())
+}
+
+/// The argument of the target call in the trace
+fn target_arg(trace: &serde_json::Value, index: usize) -> &serde_json::Value {
+  let target = trace["target"].as_u64().unwrap() as usize;
+  &trace["instrs"][target]["sem"]["Call"]["args"][index]
+}
+
+#[test]
+fn execute_expect_feeding_comparison() -> Result<(), String> {
+  let path = Path::new("tests/c_files/intrinsics/intrinsics.bc");
+  let options = TempOptions::new("execute_expect_feeding_comparison");
+  let (_, traces) = execute(path, "alloc_unlikely_null", "kzalloc", &options)?;
+  assert!(traces.len() >= 2, "Should explore both sides of the unlikely check");
+  for trace in traces {
+    let instrs = trace["instrs"].as_array().unwrap();
+    assert!(
+      instrs
+        .iter()
+        .all(|instr| instr["sem"]["Call"]["func"]["Func"] != "llvm.expect.i64"),
+      "The expect intrinsic should be modeled as its value"
+    );
+    assert!(
+      instrs
+        .iter()
+        .any(|instr| instr["sem"]["ICmp"].to_string().contains("kzalloc")),
+      "The comparison under unlikely should be on the result of the target"
+    );
+  }
+  Ok(())
+}
+
+#[test]
+fn execute_with_overflow_result() -> Result<(), String> {
+  let path = Path::new("tests/c_files/intrinsics/intrinsics.bc");
+  let options = TempOptions::new("execute_with_overflow_result");
+  let (_, traces) = execute(path, "alloc_array", "kzalloc", &options)?;
+  assert!(!traces.is_empty());
+  for trace in &traces {
+    let size = target_arg(trace, 0);
+    assert_eq!(size["Bin"]["op"], "Mul");
+    assert_eq!(size["Bin"]["op0"]["Arg"], 0);
+    assert_eq!(size["Bin"]["op1"]["Arg"], 1);
+  }
+  Ok(())
+}
+
+#[test]
+fn execute_memcpy_of_nested_fields() -> Result<(), String> {
+  let path = Path::new("tests/c_files/intrinsics/intrinsics.bc");
+  let options = TempOptions::new("execute_memcpy_of_nested_fields");
+  let (_, traces) = execute(path, "alloc_copied_size", "kzalloc", &options)?;
+  assert!(!traces.is_empty());
+  for trace in &traces {
+    let checked = trace["instrs"]
+      .as_array()
+      .unwrap()
+      .iter()
+      .find_map(|instr| instr["sem"].get("ICmp"))
+      .unwrap();
+    assert_eq!(
+      target_arg(trace, 0),
+      &checked["op0"],
+      "The size copied into the nested field should be the checked one"
+    );
+  }
+  Ok(())
+}
+
+#[test]
+fn execute_memset_of_nested_fields() -> Result<(), String> {
+  let path = Path::new("tests/c_files/intrinsics/intrinsics.bc");
+  let options = TempOptions::new("execute_memset_of_nested_fields");
+  let (_, traces) = execute(path, "alloc_cleared_size", "kzalloc", &options)?;
+  assert!(!traces.is_empty());
+  for trace in &traces {
+    assert_eq!(target_arg(trace, 0)["Int"], 0, "The nested field should be cleared");
+  }
+  Ok(())
+}
+
+#[test]
+fn execute_variadic_arguments() -> Result<(), String> {
+  let path = Path::new("tests/c_files/varargs/varargs_1.bc");
+  let mut options = TempOptions::new("execute_variadic_arguments");
+  options.loop_unroll_bound = 1;
+  let (_, traces) = execute(path, "alloc_all", "malloc", &options)?;
+  assert!(!traces.is_empty());
+  if cfg!(feature = "z3") {
+    // Reading from the registers is infeasible as they are used up
+    for trace in &traces {
+      let sizes = trace["targets"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|target| &trace["instrs"][target.as_u64().unwrap() as usize]["sem"]["Call"]["args"][0])
+        .collect::<Vec<_>>();
+      assert_eq!(
+        sizes[0]["Int"], 16,
+        "The first `va_arg` should read the first passed argument"
+      );
+      if sizes.len() > 1 {
+        assert_eq!(
+          sizes[1]["Int"], 32,
+          "The second `va_arg` should read the second passed argument"
+        );
+      }
+    }
+  }
+  assert!(traces.iter().any(|trace| target_arg(trace, 0)["Int"] == 16));
+  Ok(())
+}
+
+#[test]
+fn execute_copied_variadic_list() -> Result<(), String> {
+  let path = Path::new("tests/c_files/varargs/varargs_1.bc");
+  let mut options = TempOptions::new("execute_copied_variadic_list");
+  options.slice_depth = 2;
+  let (_, traces) = execute(path, "alloc_from_list", "malloc", &options)?;
+  assert!(
+    traces.iter().any(|trace| target_arg(trace, 0)["Int"] == 32),
+    "The copied list should continue after the argument read from the original one"
+  );
+  Ok(())
+}
+
+#[test]
+fn execute_keeping_truncated_traces() -> Result<(), String> {
+  let path = Path::new("tests/c_files/trace/long_tail.bc");
+  let mut options = TempOptions::new("execute_keeping_truncated_traces");
+  options.max_node_per_trace = 10;
+  options.keep_truncated_traces = true;
+  let (metadata, traces) = execute(path, "alloc_then_use", "kzalloc", &options)?;
+  assert_eq!(metadata.proper_trace_count, 0, "Truncated traces are not proper");
+  assert_eq!(metadata.truncated_trace_count, traces.len());
+  assert!(!traces.is_empty());
+  assert!(traces.iter().all(|trace| trace["truncated"] == "exceeding_max_length"));
+
+  // Truncated traces are numbered after the proper ones
+  for entry in fs::read_dir(options.trace_target_slice_dir("kzalloc", 0)).unwrap() {
+    let stem = entry.unwrap().path().file_stem().unwrap().to_string_lossy().to_string();
+    assert!(stem.parse::<usize>().unwrap() >= options.max_trace_per_slice());
+  }
+  Ok(())
+}
+
+#[test]
+fn execute_dropping_truncated_traces() -> Result<(), String> {
+  let path = Path::new("tests/c_files/trace/long_tail.bc");
+  let mut options = TempOptions::new("execute_dropping_truncated_traces");
+  options.max_node_per_trace = 10;
+  let (metadata, traces) = execute(path, "alloc_then_use", "kzalloc", &options)?;
+  assert!(traces.is_empty());
+  assert_eq!(metadata.truncated_trace_count, 0);
+  assert!(metadata.exceeding_length_trace_count > 0);
+  Ok(())
+}
+
+/// Number of frames of the function on the stack at the target
+fn num_target_frames_of(trace: &serde_json::Value, func: &str) -> usize {
+  let target = trace["target"].as_u64().unwrap() as usize;
+  trace["instrs"][target]["frames"]
+    .as_array()
+    .unwrap()
+    .iter()
+    .filter(|frame| *frame == func)
+    .count()
+}
+
+#[test]
+fn execute_bounded_recursion() -> Result<(), String> {
+  let path = Path::new("tests/c_files/trace/recursive.bc");
+  let mut options = TempOptions::new("execute_bounded_recursion");
+  options.max_recursion_depth = 2;
+  let (_, traces) = execute(path, "alloc_nested", "kzalloc", &options)?;
+  assert!(!traces.is_empty());
+  for trace in &traces {
+    let recursion_depth = trace["recursion_depth"].as_u64().unwrap() as usize;
+    assert!(recursion_depth <= 2, "Recursion should be unrolled up to the bound");
+    assert!(
+      num_target_frames_of(trace, "alloc_nested") <= recursion_depth + 1,
+      "Every recursive frame should be on the stack"
+    );
+  }
+  assert!(
+    traces
+      .iter()
+      .any(|trace| trace["recursion_depth"] == 2 && num_target_frames_of(trace, "alloc_nested") == 3),
+    "The target should be reached at the deepest unrolled frame"
+  );
+  Ok(())
+}
+
+#[test]
+fn execute_without_recursion() -> Result<(), String> {
+  let path = Path::new("tests/c_files/trace/recursive.bc");
+  let options = TempOptions::new("execute_without_recursion");
+  let (_, traces) = execute(path, "alloc_nested", "kzalloc", &options)?;
+  assert!(!traces.is_empty());
+  for trace in &traces {
+    assert_eq!(trace["recursion_depth"], 0);
+    assert_eq!(num_target_frames_of(trace, "alloc_nested"), 1);
+  }
+  Ok(())
+}
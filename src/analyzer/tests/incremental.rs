@@ -0,0 +1,63 @@
+use llir::*;
+use std::path::Path;
+
+use analyzer::incremental::*;
+
+fn test_num_changed_functions(prev_path: &Path, path: &Path) -> Result<usize, String> {
+  let ctx = Context::create();
+  let prev_module = ctx.load_module(prev_path)?;
+  let module = ctx.load_module(path)?;
+  let incr_ctx = IncrementalContext::new(&module, &prev_module);
+  println!("{:?}", incr_ctx.changed_functions);
+  Ok(incr_ctx.num_changed_functions())
+}
+
+#[test]
+fn incremental_same_module() -> Result<(), String> {
+  let path = Path::new("tests/c_files/basic/example_1.bc");
+  let num_changed = test_num_changed_functions(path, path)?;
+  assert_eq!(num_changed, 0, "Same module should not contain changed functions");
+  Ok(())
+}
+
+#[test]
+fn incremental_different_module() -> Result<(), String> {
+  let prev_path = Path::new("tests/c_files/basic/example_1.bc");
+  let path = Path::new("tests/c_files/basic/example_3.bc");
+  let num_changed = test_num_changed_functions(prev_path, path)?;
+  assert!(num_changed > 0, "Different modules should contain changed functions");
+  Ok(())
+}
+
+fn test_changed_functions(prev_path: &Path, path: &Path) -> Result<Vec<String>, String> {
+  let ctx = Context::create();
+  let prev_module = ctx.load_module(prev_path)?;
+  let module = ctx.load_module(path)?;
+  let incr_ctx = IncrementalContext::new(&module, &prev_module);
+  Ok(incr_ctx.changed_functions.into_iter().collect())
+}
+
+#[test]
+fn incremental_changed_ops_table() -> Result<(), String> {
+  let prev_path = Path::new("tests/c_files/incremental/ops_1.bc");
+  let path = Path::new("tests/c_files/incremental/ops_2.bc");
+  let changed = test_changed_functions(prev_path, path)?;
+  assert!(
+    changed.contains(&"run".to_string()),
+    "Reading a changed constant global should be a change"
+  );
+  assert!(!changed.contains(&"open_a".to_string()));
+  Ok(())
+}
+
+#[test]
+fn incremental_changed_struct_layout() -> Result<(), String> {
+  let prev_path = Path::new("tests/c_files/incremental/layout_1.bc");
+  let path = Path::new("tests/c_files/incremental/layout_2.bc");
+  let changed = test_changed_functions(prev_path, path)?;
+  assert!(
+    changed.contains(&"get_size".to_string()),
+    "Accessing a changed struct should be a change"
+  );
+  Ok(())
+}
@@ -0,0 +1,102 @@
+use llir::types::*;
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use analyzer::feature_extraction::*;
+use analyzer::feature_extractors::*;
+
+/// Counts the traces it extracts features from
+struct CountingFeatureExtractor {
+  num_extracted: Arc<AtomicUsize>,
+}
+
+impl FeatureExtractor for CountingFeatureExtractor {
+  fn name(&self) -> String {
+    "count".to_string()
+  }
+
+  fn filter<'ctx>(&self, _: &String, _: FunctionType<'ctx>) -> bool {
+    true
+  }
+
+  fn init(&mut self, _: usize, _: &Slice, _: usize, _: &Trace) {}
+
+  fn finalize(&mut self) {}
+
+  fn extract(&self, _: usize, _: &Slice, _: &Trace) -> serde_json::Value {
+    self.num_extracted.fetch_add(1, Ordering::SeqCst);
+    json!(true)
+  }
+}
+
+fn kzalloc_result(id: usize) -> serde_json::Value {
+  json!({ "Call": { "id": id, "func": { "Func": "kzalloc" }, "args": [{ "Int": 30 }] } })
+}
+
+/// Two calls to `kzalloc`, where only the result of the first is checked
+fn trace() -> Trace {
+  let call = |id| {
+    json!({
+      "loc": "",
+      "sem": { "Call": { "func": { "Func": "kzalloc" }, "args": [{ "Int": 30 }] } },
+      "res": kzalloc_result(id),
+    })
+  };
+  let icmp = json!({ "ICmp": { "pred": "EQ", "op0": kzalloc_result(0), "op1": "Null" } });
+  serde_json::from_value(json!({
+    "target": 0,
+    "targets": [0, 2],
+    "instrs": [
+      call(0),
+      { "loc": "", "sem": icmp, "res": icmp },
+      call(1),
+      { "loc": "", "sem": { "Call": { "func": { "Func": "use" }, "args": [kzalloc_result(1)] } }, "res": null },
+    ],
+  }))
+  .unwrap()
+}
+
+fn slice() -> Slice {
+  serde_json::from_value(json!({
+    "instr": "",
+    "entry": "probe",
+    "caller": "probe",
+    "callee": "kzalloc",
+    "functions": ["probe"],
+  }))
+  .unwrap()
+}
+
+#[test]
+fn test_occurrences() {
+  let trace = trace();
+  assert_eq!(trace.occurrences(), vec![0, 2]);
+  assert_eq!(trace.target_index(), 0);
+}
+
+#[test]
+fn test_aggregate_occurrences() {
+  let (trace, slice) = (trace(), slice());
+  let num_extracted = Arc::new(AtomicUsize::new(0));
+  let mut extractors = FeatureExtractors::new(vec![
+    Box::new(ReturnValueCheckFeatureExtractor::new()),
+    Box::new(CountingFeatureExtractor {
+      num_extracted: num_extracted.clone(),
+    }),
+  ]);
+  extractors.initialize(0, &slice, 1, &trace);
+  extractors.finalize();
+  let features = extractors.extract_features(0, &slice, &trace);
+
+  assert_eq!(features["ret.check"]["checked"], true);
+  assert_eq!(features["ret.check.all"]["all_checked"], false);
+  assert_eq!(features["ret.check.all"]["unchecked"], json!([1]));
+  assert_eq!(features["num_occurrences"], 2);
+  assert_eq!(features["occurrences"][1]["ret.check"]["checked"], false);
+
+  // Extractors not aggregating occurrences only run at the first one
+  assert_eq!(features["count"], true);
+  assert!(features.get("count.all").is_none());
+  assert_eq!(num_extracted.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,57 @@
+use rand::{rngs::StdRng, SeedableRng};
+
+use analyzer::utils::*;
+
+#[test]
+fn test_allocation_within_budget() {
+  let allocation = stratified_allocation(&vec![3, 0, 2], 10);
+  assert_eq!(allocation, vec![3, 0, 2], "Every item should be kept within budget");
+}
+
+#[test]
+fn test_allocation_proportional() {
+  let allocation = stratified_allocation(&vec![100, 10, 1], 20);
+  assert_eq!(
+    allocation.iter().sum::<usize>(),
+    20,
+    "Allocation should use the whole budget"
+  );
+  assert!(allocation.iter().all(|n| *n >= 1), "Every stratum should be covered");
+  assert!(allocation[0] > allocation[1], "Larger strata should get more samples");
+}
+
+#[test]
+fn test_allocation_small_budget() {
+  let allocation = stratified_allocation(&vec![1, 5, 3], 2);
+  assert_eq!(allocation, vec![0, 1, 1], "Largest strata should be preferred");
+}
+
+#[test]
+fn test_stratified_sample_weights() {
+  let items = (0..100).collect::<Vec<usize>>();
+  let mut rng = StdRng::seed_from_u64(12345);
+  let sampled = stratified_sample(items, |i| i % 3, 10, &mut rng);
+  assert_eq!(sampled.len(), 10, "Should sample exactly the budget");
+  let total_weight: f64 = sampled.iter().map(|(_, w)| w).sum();
+  assert!(
+    (total_weight - 100.0).abs() < 1e-6,
+    "Weights should sum up to the number of items"
+  );
+}
+
+#[test]
+fn test_stratified_sample_weights_small_budget() {
+  let items = (0..9).collect::<Vec<usize>>();
+  let mut rng = StdRng::seed_from_u64(12345);
+  let sampled = stratified_sample(items, |i| i / 4, 2, &mut rng);
+  assert_eq!(sampled.len(), 2, "Should sample exactly the budget");
+  assert!(
+    sampled.iter().all(|(i, _)| *i < 8),
+    "Smallest stratum should be dropped"
+  );
+  let total_weight: f64 = sampled.iter().map(|(_, w)| w).sum();
+  assert!(
+    (total_weight - 9.0).abs() < 1e-6,
+    "Weights of the dropped strata should be folded into the sampled ones"
+  );
+}
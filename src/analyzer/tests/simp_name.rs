@@ -0,0 +1,26 @@
+use analyzer::options::*;
+use analyzer::utils::*;
+
+#[test]
+fn test_simplify_c_name() {
+  assert_eq!(simplify_name("kmalloc"), "kmalloc");
+  assert_eq!(simplify_name("dev_err.123"), "dev_err");
+  assert_eq!(simplify_name("llvm.memcpy.p0i8.p0i8.i64"), "memcpy");
+}
+
+#[test]
+fn test_simplify_cpp_name() {
+  assert_eq!(simplify_name("_ZN3foo3barEv"), "foo::bar");
+  assert_eq!(simplify_name("_ZN3foo3barEi"), "foo::bar");
+  assert_eq!(simplify_name("_ZN3foo3barEv.cold"), "foo::bar");
+  assert_eq!(simplify_name("_Znot_a_symbol"), "_Znot_a_symbol");
+}
+
+#[test]
+fn test_cpp_name_dir() {
+  assert_eq!(target_dir_name("kmalloc"), "kmalloc");
+  assert_eq!(target_dir_name("foo::bar"), "foo::bar");
+  assert_eq!(target_dir_name("Vec::operator/"), "Vec::operator_");
+  assert_eq!(target_dir_name("foo<int, char>"), "foo_int__char_");
+  assert_eq!(target_dir_name("../etc"), ".._etc");
+}
@@ -0,0 +1,356 @@
+use llir::{values::*, *};
+use std::path::Path;
+
+use analyzer::call_graph::*;
+use analyzer::options::*;
+use analyzer::slicer::*;
+use analyzer::target_spec::*;
+use analyzer::utils::*;
+
+struct TempOptions {
+  slice_depth: usize,
+  adaptive_slice_depth: bool,
+  max_slice_depth: usize,
+  entry_policies: Vec<EntryPolicy>,
+  entry_patterns: Option<String>,
+  stop_at_entries: bool,
+  max_entry_climb: usize,
+  relatedness_policies: Vec<RelatednessPolicy>,
+}
+
+impl TempOptions {
+  fn new() -> Self {
+    Self {
+      slice_depth: 1,
+      adaptive_slice_depth: false,
+      max_slice_depth: 1,
+      entry_policies: vec![EntryPolicy::Any],
+      entry_patterns: None,
+      stop_at_entries: false,
+      max_entry_climb: 2,
+      relatedness_policies: vec![],
+    }
+  }
+}
+
+impl GeneralOptions for TempOptions {
+  fn use_serial(&self) -> bool {
+    true
+  }
+
+  fn seed(&self) -> u64 {
+    12345
+  }
+}
+
+impl SlicerOptions for TempOptions {
+  fn no_reduce_slice(&self) -> bool {
+    false
+  }
+
+  fn slice_depth(&self) -> usize {
+    self.slice_depth
+  }
+
+  fn adaptive_slice_depth(&self) -> bool {
+    self.adaptive_slice_depth
+  }
+
+  fn max_slice_depth(&self) -> usize {
+    self.max_slice_depth
+  }
+
+  fn entry_filter(&self) -> &Option<String> {
+    &None
+  }
+
+  fn entry_policies(&self) -> &Vec<EntryPolicy> {
+    &self.entry_policies
+  }
+
+  fn entry_patterns(&self) -> &Option<String> {
+    &self.entry_patterns
+  }
+
+  fn stop_at_entries(&self) -> bool {
+    self.stop_at_entries
+  }
+
+  fn max_entry_climb(&self) -> usize {
+    self.max_entry_climb
+  }
+
+  fn max_entries_per_edge(&self) -> Option<usize> {
+    None
+  }
+
+  fn target_inclusion_filter(&self) -> &Option<String> {
+    &None
+  }
+
+  fn target_exclusion_filter(&self) -> &Option<String> {
+    &None
+  }
+
+  fn target_spec(&self) -> &Option<TargetSpec> {
+    &None
+  }
+
+  fn use_regex_filter(&self) -> bool {
+    false
+  }
+
+  fn max_avg_num_blocks(&self) -> usize {
+    1000
+  }
+
+  fn max_edges_per_target(&self) -> Option<usize> {
+    None
+  }
+
+  fn sampling_stratum(&self) -> SamplingStratum {
+    SamplingStratum::File
+  }
+
+  fn relatedness_policies(&self) -> &Vec<RelatednessPolicy> {
+    &self.relatedness_policies
+  }
+
+  fn relatedness_prefix_length(&self) -> usize {
+    4
+  }
+
+  fn dump_reduction_report(&self) -> bool {
+    true
+  }
+}
+
+impl CallGraphOptions for TempOptions {
+  fn remove_llvm_funcs(&self) -> bool {
+    true
+  }
+}
+
+/// The call to the target inside of the caller
+fn target_call<'ctx>(caller: Function<'ctx>, target: &str) -> CallInstruction<'ctx> {
+  caller
+    .iter_instructions()
+    .find_map(|instr| match instr {
+      Instruction::Call(call) if call.callee_function().map(|f| f.simp_name()) == Some(target.to_string()) => {
+        Some(call)
+      }
+      _ => None,
+    })
+    .unwrap()
+}
+
+/// Names of the functions related to `dev_alloc` in `probe` under the policy
+fn related_functions(policy: RelatednessPolicy) -> Result<Vec<String>, String> {
+  let ctx = Context::create();
+  let module = ctx.load_module(Path::new("tests/c_files/slicer/relatedness.bc"))?;
+  let mut options = TempOptions::new();
+  options.relatedness_policies = vec![policy];
+  let caller = module.get_function("probe").unwrap();
+  let instr = target_call(caller, "dev_alloc");
+  let checker = RelatednessChecker::new(instr, instr.callee_function().unwrap(), &options);
+  Ok(
+    module
+      .iter_functions()
+      .filter(|func| checker.related_by(func) == Some(policy))
+      .map(|func| func.simp_name())
+      .collect(),
+  )
+}
+
+#[test]
+fn relatedness_name_prefix() -> Result<(), String> {
+  let related = related_functions(RelatednessPolicy::NamePrefix)?;
+  assert!(related.contains(&"dev_free".to_string()));
+  assert!(related.contains(&"dev_register".to_string()));
+  assert!(!related.contains(&"buf_len".to_string()));
+  Ok(())
+}
+
+#[test]
+fn relatedness_signature_structs() -> Result<(), String> {
+  let related = related_functions(RelatednessPolicy::SignatureStructs)?;
+  assert!(related.contains(&"dev_register".to_string()));
+  assert!(!related.contains(&"buf_len".to_string()));
+  assert!(!related.contains(&"touch_global".to_string()));
+  Ok(())
+}
+
+#[test]
+fn relatedness_body_structs() -> Result<(), String> {
+  let related = related_functions(RelatednessPolicy::BodyStructs)?;
+  assert!(
+    related.contains(&"touch_global".to_string()),
+    "Accessing a global struct through a GEP operand should relate the function"
+  );
+  assert!(!related.contains(&"buf_len".to_string()));
+  Ok(())
+}
+
+#[test]
+fn relatedness_data_flow() -> Result<(), String> {
+  let related = related_functions(RelatednessPolicy::DataFlow)?;
+  assert!(related.contains(&"buf_len".to_string()));
+  assert!(related.contains(&"dev_free".to_string()));
+  assert!(!related.contains(&"log_msg".to_string()));
+  Ok(())
+}
+
+#[test]
+fn relatedness_same_file() -> Result<(), String> {
+  let related = related_functions(RelatednessPolicy::SameFile)?;
+  assert!(related.contains(&"touch_global".to_string()));
+  assert!(
+    !related.contains(&"log_msg".to_string()),
+    "Declared functions have no file"
+  );
+  Ok(())
+}
+
+/// Names of the entries of the `helper` -> `target` call edge
+fn entries(policy: EntryPolicy, slice_depth: usize, stop_at_entries: bool) -> Result<Vec<String>, String> {
+  let mut options = TempOptions::new();
+  options.slice_depth = slice_depth;
+  options.entry_policies = vec![policy];
+  options.stop_at_entries = stop_at_entries;
+  entries_with_options(&options)
+}
+
+fn entries_with_options(options: &TempOptions) -> Result<Vec<String>, String> {
+  let ctx = Context::create();
+  let module = ctx.load_module(Path::new("tests/c_files/slicer/entry.bc"))?;
+  let entry_matcher = EntryMatcher::new(options)?;
+  let call_graph = CallGraph::from_module(&module, options);
+  let caller_id = call_graph.function_id_map[&module.get_function("helper").unwrap()];
+  let target_id = call_graph.function_id_map[&module.get_function("target").unwrap()];
+  let edge_id = call_graph.graph.find_edge(caller_id, target_id).unwrap();
+  let mut entries = call_graph
+    .find_entries(edge_id, options.slice_depth, &entry_matcher, options)
+    .into_iter()
+    .map(|func_id| call_graph.graph[func_id].simp_name())
+    .collect::<Vec<_>>();
+  entries.sort();
+  Ok(entries)
+}
+
+#[test]
+fn entry_any() -> Result<(), String> {
+  assert_eq!(entries(EntryPolicy::Any, 1, false)?, vec!["middle"]);
+  assert_eq!(entries(EntryPolicy::Any, 2, false)?, vec!["api_call", "callback"]);
+  Ok(())
+}
+
+#[test]
+fn entry_external() -> Result<(), String> {
+  assert_eq!(
+    entries(EntryPolicy::External, 1, false)?,
+    vec!["api_call"],
+    "Static functions at the slice depth should climb further to an external entry"
+  );
+  assert_eq!(
+    entries(EntryPolicy::External, 3, false)?,
+    vec!["dispatch"],
+    "Matching callers below the slice depth should not stop the climbing"
+  );
+  assert_eq!(entries(EntryPolicy::External, 3, true)?, vec!["api_call"]);
+  Ok(())
+}
+
+#[test]
+fn entry_climb_is_capped() -> Result<(), String> {
+  let mut options = TempOptions::new();
+  options.entry_policies = vec![EntryPolicy::KnownPattern];
+  options.max_entry_climb = 1;
+  assert!(
+    entries_with_options(&options)?.is_empty(),
+    "The known entry two callers past the slice depth should not be reached"
+  );
+  options.max_entry_climb = 0;
+  options.entry_policies = vec![EntryPolicy::External];
+  assert!(
+    entries_with_options(&options)?.is_empty(),
+    "Non-matching callers at the slice depth should drop the edge without climbing"
+  );
+  Ok(())
+}
+
+#[test]
+fn malformed_entry_patterns() {
+  let mut options = TempOptions::new();
+  options.entry_patterns = Some("(probe".to_string());
+  assert!(EntryMatcher::new(&options).is_err());
+  options.entry_patterns = Some("probe$".to_string());
+  assert!(EntryMatcher::new(&options).is_ok());
+}
+
+#[test]
+fn entry_address_taken() -> Result<(), String> {
+  assert_eq!(entries(EntryPolicy::AddressTaken, 1, false)?, vec!["callback"]);
+  Ok(())
+}
+
+#[test]
+fn entry_known_pattern() -> Result<(), String> {
+  assert_eq!(entries(EntryPolicy::KnownPattern, 1, false)?, vec!["dev_probe"]);
+  Ok(())
+}
+
+/// Adaptive slice depth of the call edge from the caller to the callee
+fn adaptive_depth(caller: &str, callee: &str, slice_depth: usize, max_slice_depth: usize) -> Result<usize, String> {
+  let ctx = Context::create();
+  let module = ctx.load_module(Path::new("tests/c_files/slicer/depth.bc"))?;
+  let mut options = TempOptions::new();
+  options.slice_depth = slice_depth;
+  options.adaptive_slice_depth = true;
+  options.max_slice_depth = max_slice_depth;
+  let call_graph = CallGraph::from_module(&module, &options);
+  let caller_id = call_graph.function_id_map[&module.get_function(caller).unwrap()];
+  let callee_id = call_graph.function_id_map[&module.get_function(callee).unwrap()];
+  let edge_id = call_graph.graph.find_edge(caller_id, callee_id).unwrap();
+  Ok(call_graph.adaptive_slice_depth(edge_id, &options))
+}
+
+#[test]
+fn adaptive_depth_escaping_result() -> Result<(), String> {
+  assert_eq!(adaptive_depth("result_escapes", "target", 0, 3)?, 2);
+  assert_eq!(
+    adaptive_depth("result_escapes", "target", 0, 1)?,
+    1,
+    "Escaping levels should be capped by the max slice depth"
+  );
+  Ok(())
+}
+
+#[test]
+fn adaptive_depth_returned_int_argument() -> Result<(), String> {
+  assert_eq!(
+    adaptive_depth("int_argument_returned", "target", 0, 3)?,
+    0,
+    "Returning an integer argument of the call should not count as an escape"
+  );
+  Ok(())
+}
+
+#[test]
+fn adaptive_depth_pointer_argument() -> Result<(), String> {
+  assert_eq!(
+    adaptive_depth("pointer_argument", "fill", 0, 3)?,
+    1,
+    "Passing a parameter of the caller as pointer should count as an escape"
+  );
+  Ok(())
+}
+
+#[test]
+fn adaptive_depth_explicit_slice_depth() -> Result<(), String> {
+  assert_eq!(
+    adaptive_depth("int_argument_returned", "target", 2, 1)?,
+    2,
+    "Explicit slice depth should not be capped by the max slice depth"
+  );
+  Ok(())
+}
@@ -0,0 +1,68 @@
+use analyzer::semantics::rced::*;
+use analyzer::symbolic_execution::*;
+use llir::values::ICmpPredicate;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+
+struct CountingBackend {
+  num_queries: Rc<Cell<usize>>,
+}
+
+impl SolverBackend for CountingBackend {
+  fn check(&mut self, constraints: &[InternedConstraint]) -> bool {
+    self.num_queries.set(self.num_queries.get() + 1);
+    constraints.len() < 2
+  }
+}
+
+fn constraint(op1: i64, branch: bool) -> Constraint {
+  let cond = Value::ICmp {
+    pred: ICmpPredicate::EQ,
+    op0: Rc::new(Value::Arg(0)),
+    op1: Rc::new(Value::Int(op1)),
+  }
+  .as_comparison()
+  .unwrap();
+  Constraint {
+    cond,
+    branch,
+    loc: String::new(),
+    node_id: 0,
+  }
+}
+
+#[test]
+fn test_solver_cache() {
+  let num_queries = Rc::new(Cell::new(0));
+  let mut solver = Solver::with_backend(Box::new(CountingBackend {
+    num_queries: num_queries.clone(),
+  }));
+  let path = vec![constraint(1, true)];
+  assert!(solver.sat(&path));
+  assert!(solver.sat(&path));
+  assert_eq!(num_queries.get(), 1);
+
+  // The same constraints in another order share the cached result
+  let other = constraint(2, false);
+  assert!(!solver.sat_with(&path, &other.cond, other.branch));
+  assert!(!solver.sat(&vec![other, constraint(1, true)]));
+  assert_eq!(num_queries.get(), 2);
+}
+
+#[test]
+fn validate_missing_smtlib_solver() {
+  let timeout = Duration::from_millis(1000);
+  assert!(Solver::validate(SolverKind::SmtLib, "nonexistent-solver-command -in", timeout).is_err());
+  assert!(Solver::validate(SolverKind::SmtLib, "", timeout).is_err());
+  assert!(Solver::validate(SolverKind::AlwaysSat, "", timeout).is_ok());
+}
+
+#[test]
+fn smtlib_solver_without_answer_times_out() {
+  // `cat` echoes the queries back and never answers `check-sat`
+  let timeout = Duration::from_millis(100);
+  assert!(Solver::validate(SolverKind::SmtLib, "cat", timeout).is_err());
+  let mut backend = SmtLibBackend::new("cat", timeout);
+  assert!(backend.check(&[]), "A query without answer should be treated as sat");
+}
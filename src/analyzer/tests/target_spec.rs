@@ -0,0 +1,289 @@
+use llir::*;
+use std::path::Path;
+
+use analyzer::call_graph::*;
+use analyzer::options::*;
+use analyzer::slicer::*;
+use analyzer::target_spec::*;
+
+struct TempOptions {
+  entry_policies: Vec<EntryPolicy>,
+  relatedness_policies: Vec<RelatednessPolicy>,
+  target_spec: Option<TargetSpec>,
+}
+
+impl TempOptions {
+  fn new(target_spec: TargetSpec) -> Self {
+    Self {
+      entry_policies: vec![EntryPolicy::Any],
+      relatedness_policies: vec![],
+      target_spec: Some(target_spec),
+    }
+  }
+}
+
+impl GeneralOptions for TempOptions {
+  fn use_serial(&self) -> bool {
+    true
+  }
+
+  fn seed(&self) -> u64 {
+    12345
+  }
+}
+
+impl SlicerOptions for TempOptions {
+  fn no_reduce_slice(&self) -> bool {
+    true
+  }
+
+  fn slice_depth(&self) -> usize {
+    1
+  }
+
+  fn adaptive_slice_depth(&self) -> bool {
+    false
+  }
+
+  fn max_slice_depth(&self) -> usize {
+    1
+  }
+
+  fn entry_filter(&self) -> &Option<String> {
+    &None
+  }
+
+  fn entry_policies(&self) -> &Vec<EntryPolicy> {
+    &self.entry_policies
+  }
+
+  fn entry_patterns(&self) -> &Option<String> {
+    &None
+  }
+
+  fn stop_at_entries(&self) -> bool {
+    false
+  }
+
+  fn max_entry_climb(&self) -> usize {
+    2
+  }
+
+  fn max_entries_per_edge(&self) -> Option<usize> {
+    None
+  }
+
+  fn target_inclusion_filter(&self) -> &Option<String> {
+    &None
+  }
+
+  fn target_exclusion_filter(&self) -> &Option<String> {
+    &None
+  }
+
+  fn target_spec(&self) -> &Option<TargetSpec> {
+    &self.target_spec
+  }
+
+  fn use_regex_filter(&self) -> bool {
+    false
+  }
+
+  fn max_avg_num_blocks(&self) -> usize {
+    1000
+  }
+
+  fn max_edges_per_target(&self) -> Option<usize> {
+    None
+  }
+
+  fn sampling_stratum(&self) -> SamplingStratum {
+    SamplingStratum::File
+  }
+
+  fn relatedness_policies(&self) -> &Vec<RelatednessPolicy> {
+    &self.relatedness_policies
+  }
+
+  fn relatedness_prefix_length(&self) -> usize {
+    4
+  }
+
+  fn dump_reduction_report(&self) -> bool {
+    false
+  }
+}
+
+impl CallGraphOptions for TempOptions {
+  fn remove_llvm_funcs(&self) -> bool {
+    true
+  }
+}
+
+#[test]
+fn parse_toml_target_spec() -> Result<(), String> {
+  let spec = TargetSpec::parse(
+    r#"
+    [[targets]]
+    name = "kmalloc"
+    options = { slice_depth = 2, max_trace_per_slice = 100 }
+
+    [[targets]]
+    regex = "^dev_.*"
+
+    [[targets]]
+    signature = { returns = "pointer", args = ["struct device *"] }
+    "#,
+    false,
+  )?;
+  assert_eq!(spec.targets.len(), 3);
+  assert_eq!(spec.targets[0].options.slice_depth, Some(2));
+  assert_eq!(spec.targets[0].options.max_work, None);
+  assert!(spec.targets[1].regex.is_some());
+  assert_eq!(spec.targets[2].signature.as_ref().unwrap().args.len(), 1);
+  Ok(())
+}
+
+#[test]
+fn parse_json_target_spec() -> Result<(), String> {
+  let spec = TargetSpec::parse(
+    r#"{ "targets": [{ "name": "malloc", "options": { "max_work": 10 } }] }"#,
+    true,
+  )?;
+  assert_eq!(spec.targets[0].name, Some("malloc".to_string()));
+  assert_eq!(spec.targets[0].options.max_work, Some(10));
+  Ok(())
+}
+
+#[test]
+fn reject_target_spec_without_matcher() {
+  let spec = TargetSpec::parse("[[targets]]\noptions = { max_work = 10 }", false);
+  assert!(
+    spec.is_err(),
+    "Target without name, regex or signature should be rejected"
+  );
+}
+
+#[test]
+fn reject_unknown_target_spec_fields() {
+  let spec = TargetSpec::parse("[[targets]]\nname = \"kmalloc\"\noptions = { slice_dpeth = 2 }", false);
+  assert!(spec.is_err(), "Misspelled option should be rejected");
+  let spec = TargetSpec::parse(
+    "[[targets]]\nname = \"kmalloc\"\nsignatures = { returns = \"pointer\" }",
+    false,
+  );
+  assert!(spec.is_err(), "Misspelled matcher should be rejected");
+  let spec = TargetSpec::parse(
+    r#"{ "targets": [{ "name": "malloc", "option": { "max_work": 10 } }] }"#,
+    true,
+  );
+  assert!(spec.is_err(), "Misspelled options should be rejected");
+}
+
+/// Names of the functions in `targets.c` matched by the spec
+fn matched_functions(spec: &str) -> Result<Vec<String>, String> {
+  let spec = TargetSpec::parse(spec, false)?;
+  let ctx = Context::create();
+  let module = ctx.load_module(Path::new("tests/c_files/target_spec/targets.bc"))?;
+  let mut names = ["dev_alloc", "dev_register", "buf_alloc", "dev_bind", "probe"]
+    .iter()
+    .filter(|name| spec.matches(&module.get_function(name).unwrap()))
+    .map(|name| name.to_string())
+    .collect::<Vec<_>>();
+  names.sort();
+  Ok(names)
+}
+
+#[test]
+fn match_signature_returns() -> Result<(), String> {
+  assert_eq!(
+    matched_functions("[[targets]]\nsignature = { returns = \"struct device *\" }")?,
+    vec!["dev_alloc", "probe"]
+  );
+  assert_eq!(
+    matched_functions("[[targets]]\nsignature = { returns = \"pointer\", num_args = 1 }")?,
+    vec!["buf_alloc", "dev_alloc", "probe"]
+  );
+  Ok(())
+}
+
+#[test]
+fn match_signature_args() -> Result<(), String> {
+  assert_eq!(
+    matched_functions("[[targets]]\nsignature = { args = [\"struct device *\"] }")?,
+    vec!["dev_bind", "dev_register"],
+    "Struct names with a numeric suffix should match the plain struct"
+  );
+  assert_eq!(
+    matched_functions("[[targets]]\nsignature = { args = [\"*\", \"integer\"], num_args = 2 }")?,
+    vec!["dev_register"]
+  );
+  assert_eq!(
+    matched_functions("[[targets]]\nsignature = { args = [\"*\", \"*\", \"*\"] }")?,
+    Vec::<String>::new(),
+    "Patterns of more arguments than declared should not match"
+  );
+  Ok(())
+}
+
+#[test]
+fn match_name_and_regex() -> Result<(), String> {
+  assert_eq!(
+    matched_functions("[[targets]]\nname = \"dev_alloc\"")?,
+    vec!["dev_alloc"]
+  );
+  assert_eq!(
+    matched_functions("[[targets]]\nregex = \"^dev_\"")?,
+    vec!["dev_alloc", "dev_bind", "dev_register"]
+  );
+  assert_eq!(
+    matched_functions("[[targets]]\nregex = \"^dev_\"\nsignature = { returns = \"pointer\" }")?,
+    vec!["dev_alloc"],
+    "Every matcher of an entry should match"
+  );
+  Ok(())
+}
+
+#[test]
+fn target_overrides_reach_slicer() -> Result<(), String> {
+  let spec = TargetSpec::parse(
+    r#"
+    [[targets]]
+    name = "dev_alloc"
+    options = { slice_depth = 2 }
+
+    [[targets]]
+    regex = "^dev_"
+    "#,
+    false,
+  )?;
+  let options = TempOptions::new(spec);
+  let ctx = Context::create();
+  let module = ctx.load_module(Path::new("tests/c_files/target_spec/targets.bc"))?;
+  let call_graph = CallGraph::from_module(&module, &options);
+  let target_edges_map = TargetEdgesMap::from_call_graph(&call_graph, &options)?;
+  assert!(
+    !target_edges_map.contains_key("buf_alloc"),
+    "Unmatched functions are not targets"
+  );
+  let entry_matcher = EntryMatcher::new(&options)?;
+  let target_slices_map =
+    TargetSlicesMap::from_target_edges_map(&target_edges_map, &call_graph, &entry_matcher, &options);
+  let depths_of = |target: &str| {
+    target_slices_map[target]
+      .iter()
+      .map(|slice| slice.depth)
+      .collect::<Vec<_>>()
+  };
+  assert_eq!(
+    depths_of("dev_alloc"),
+    vec![2],
+    "The target should be sliced with its overridden depth"
+  );
+  assert_eq!(
+    depths_of("dev_register"),
+    vec![1],
+    "Other targets should keep the global depth"
+  );
+  Ok(())
+}
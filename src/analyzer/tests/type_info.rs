@@ -0,0 +1,24 @@
+use analyzer::semantics::*;
+
+#[test]
+fn test_type_info_json() {
+  let ty = TypeInfo {
+    kind: TypeKind::Pointer,
+    width: None,
+    struct_name: None,
+    element: Some(Box::new(TypeInfo {
+      kind: TypeKind::Struct,
+      width: None,
+      struct_name: Some("struct.device".to_string()),
+      element: None,
+    })),
+  };
+  let json = serde_json::to_string(&ty).unwrap();
+  assert_eq!(
+    json,
+    "{\"kind\":\"Pointer\",\"element\":{\"kind\":\"Struct\",\"struct_name\":\"struct.device\"}}"
+  );
+  let ty: TypeInfo = serde_json::from_str(&json).unwrap();
+  assert!(ty.is_pointer());
+  assert_eq!(ty.pointee_struct_name(), Some(&"struct.device".to_string()));
+}
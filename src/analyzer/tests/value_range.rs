@@ -0,0 +1,48 @@
+use analyzer::feature_extraction::*;
+use analyzer::feature_extractors::*;
+use analyzer::semantics::boxed::*;
+use llir::values::ICmpPredicate;
+
+fn constraint(pred: ICmpPredicate, op1: i64, branch: bool) -> Constraint {
+  let cond = Value::ICmp {
+    pred,
+    op0: Box::new(Value::Arg(0)),
+    op1: Box::new(Value::Int(op1)),
+  }
+  .as_comparison()
+  .unwrap();
+  Constraint {
+    cond,
+    branch,
+    loc: String::new(),
+    node: 0,
+  }
+}
+
+#[test]
+fn test_value_range_of_checked_argument() {
+  let greater = constraint(ICmpPredicate::SGT, 5, true);
+  let bounded = constraint(ICmpPredicate::SGE, 100, false);
+  let range = value_range(&Value::Arg(0), None, &[&greater, &bounded]);
+  if cfg!(feature = "z3") {
+    assert_eq!(range["pos"], true);
+    assert_eq!(range["non_zero"], true);
+    assert_eq!(range["zero"], false);
+    assert_eq!(range["lower"], 5);
+    assert_eq!(range["upper"], 100);
+  } else {
+    assert!(range.is_null(), "Value ranges should be null without z3");
+  }
+}
+
+#[test]
+fn test_value_range_of_unconstrained_argument() {
+  let range = value_range(&Value::Arg(0), None, &[]);
+  if cfg!(feature = "z3") {
+    assert_eq!(range["non_zero"], false);
+    assert_eq!(range["bounded_above"], false);
+    assert_eq!(range["bounded_below"], false);
+  } else {
+    assert!(range.is_null(), "Value ranges should be null without z3");
+  }
+}